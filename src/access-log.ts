@@ -0,0 +1,53 @@
+import * as express from 'express';
+import {createWriteStream} from 'fs';
+import {ACCESS_LOG, ACCESS_LOG_FORMAT} from './config';
+
+const MONTHS = ['Jan', 'Feb', 'Mar', 'Apr', 'May', 'Jun', 'Jul', 'Aug', 'Sep', 'Oct', 'Nov', 'Dec'];
+
+function open(): NodeJS.WritableStream | undefined {
+  if (!ACCESS_LOG) {
+    return undefined;
+  }
+
+  const stream = createWriteStream(ACCESS_LOG, {flags: 'a'});
+  stream.on('error', error => process.stderr.write(`Writing the access log failed: ${error.message}\n`));
+
+  return stream;
+}
+
+const stream = open();
+
+// 10/Oct/2000:13:55:36 +0000
+function timestamp(date: Date): string {
+  const pad = (value: number) => String(value).padStart(2, '0');
+  return `${pad(date.getUTCDate())}/${MONTHS[date.getUTCMonth()]}/${date.getUTCFullYear()}:${pad(date.getUTCHours())}:${pad(date.getUTCMinutes())}:${pad(date.getUTCSeconds())} +0000`;
+}
+
+function quote(value: string | undefined): string {
+  return value ? `"${value.replace(/[\\"]/g, char => `\\${char}`).replace(/[\x00-\x1f\x7f]/g, '')}"` : '"-"';
+}
+
+/**
+ * Writes requests to ACCESS_LOG in the common or combined log format of
+ * classic web servers. Like the request log, the query string is omitted.
+ */
+export function accessLog(req: express.Request, res: express.Response, next: express.NextFunction) {
+  if (!stream) {
+    next();
+    return;
+  }
+
+  const received = new Date();
+
+  res.on('finish', () => {
+    const request = `${req.method} ${req.originalUrl.split('?')[0]} HTTP/${req.httpVersion}`;
+    const length = res.getHeader('content-length');
+    const line = `${req.ip} - - [${timestamp(received)}] ${quote(request)} ${res.statusCode} ${length ?? '-'}`;
+
+    stream.write(ACCESS_LOG_FORMAT === 'combined'
+      ? `${line} ${quote(req.get('referer'))} ${quote(req.get('user-agent'))}\n`
+      : `${line}\n`);
+  });
+
+  next();
+}
@@ -0,0 +1,87 @@
+import {errors, JSONWebKeySet, JWKS, JWT} from 'jose';
+import {CLOCK_TOLERANCE, TOKEN_EXCHANGE, TOKEN_EXCHANGE_AUDIENCE} from './config';
+import {http} from './http-client';
+import {introspect} from './introspection';
+import {JitsiUser} from './jitsi';
+import {hostGroups} from './hosts';
+import {getClient} from './oidc';
+import {lacksParticipantRole} from './visitors';
+
+// keys are fetched again if a token is signed with an unknown key, at most once per interval
+const JWKS_REFRESH_INTERVAL = 60 * 1000;
+
+let keyStore: JWKS.KeyStore | undefined;
+let keysFetched = 0;
+
+async function keys(refresh: boolean): Promise<JWKS.KeyStore> {
+  if (keyStore && (!refresh || Date.now() - keysFetched < JWKS_REFRESH_INTERVAL)) {
+    return keyStore;
+  }
+
+  const {issuer} = await getClient();
+  const jwks = await http(issuer.metadata.jwks_uri as string).json<JSONWebKeySet>();
+
+  keyStore = JWKS.asKeyStore(jwks);
+  keysFetched = Date.now();
+
+  return keyStore;
+}
+
+/**
+ * Validates a JWT access token (e.g. of keycloak) using the keys of the
+ * default provider.
+ */
+async function verifyJwt(token: string): Promise<Record<string, unknown> | undefined> {
+  const {issuer} = await getClient();
+  const options = {
+    issuer: issuer.metadata.issuer,
+    audience: TOKEN_EXCHANGE_AUDIENCE,
+    clockTolerance: `${CLOCK_TOLERANCE}s`,
+  };
+
+  try {
+    return JWT.verify(token, await keys(false), options) as Record<string, unknown>;
+  }
+  catch (error) {
+    if (error instanceof errors.JWKSNoMatchingKey) {
+      return JWT.verify(token, await keys(true), options) as Record<string, unknown>;
+    }
+
+    throw error;
+  }
+}
+
+/**
+ * Validates an access token of the identity provider, using its keys or the
+ * introspection endpoint (TOKEN_EXCHANGE), and returns the user.
+ */
+export async function verifyAccessToken(token: string): Promise<JitsiUser | undefined> {
+  if (TOKEN_EXCHANGE === 'introspection') {
+    return introspect(token);
+  }
+
+  try {
+    const claims = await verifyJwt(token);
+
+    if (!claims || typeof claims.sub !== 'string') {
+      return undefined;
+    }
+
+    const {sub, name, preferred_username, email} = claims as Record<string, string | undefined>;
+
+    return {
+      id: sub,
+      name: name ?? preferred_username ?? '',
+      email: email ?? '',
+      ...(lacksParticipantRole(claims) ? {moderator: false, visitor: true} : {}),
+      ...hostGroups(claims),
+    };
+  }
+  catch (error) {
+    if (error instanceof errors.JOSEError) {
+      return undefined;
+    }
+
+    throw error;
+  }
+}
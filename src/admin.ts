@@ -0,0 +1,113 @@
+import * as express from 'express';
+import {HTTPError} from 'got';
+import {createHash, timingSafeEqual} from 'crypto';
+import {ADMIN_TOKEN, BODY_LIMIT, PROSODY_URL} from './config';
+import {isMaintenance, setMaintenance} from './maintenance';
+import * as log from './log';
+import {audit} from './audit';
+import {knownRooms, pendingLogins, recentJoins} from './logins';
+import {endMeeting, kickParticipant} from './prosody';
+import {catchErrors} from './errors';
+
+function digest(value: string): Buffer {
+  return createHash('sha256').update(value).digest();
+}
+
+/**
+ * Admin endpoints require ADMIN_TOKEN as bearer token and are disabled
+ * without it.
+ */
+function authenticate(req: express.Request, res: express.Response, next: express.NextFunction) {
+  const [scheme, token] = req.header('authorization')?.split(' ') ?? [];
+
+  if (!ADMIN_TOKEN || scheme?.toLowerCase() !== 'bearer' || !token || !timingSafeEqual(digest(token), digest(ADMIN_TOKEN))) {
+    audit(req, 'access_denied', {flow: 'admin', reason: 'unauthorized'});
+    res.status(401).json({error: 'unauthorized'});
+    return;
+  }
+
+  next();
+}
+
+/**
+ * Answers prosody errors with 502, except for unknown rooms and participants,
+ * other errors are thrown again.
+ */
+function prosodyError(res: express.Response, error: unknown) {
+  if (error instanceof HTTPError && error.response.statusCode === 404) {
+    res.status(404).json({error: 'not_found'});
+    return;
+  }
+
+  if (error instanceof HTTPError) {
+    log.error(`Prosody responded with ${error.response.statusCode}.`);
+    res.status(502).json({error: 'prosody_error'});
+    return;
+  }
+
+  throw error;
+}
+
+export const admin = express.Router();
+
+admin.use(authenticate);
+admin.use(express.json({limit: BODY_LIMIT}));
+
+admin.get('/maintenance', (req, res) => {
+  res.json({enabled: isMaintenance()});
+});
+
+admin.put('/maintenance', (req, res) => {
+  const {enabled} = req.body ?? {};
+
+  if (typeof enabled !== 'boolean') {
+    res.status(400).json({error: 'invalid_request'});
+    return;
+  }
+
+  setMaintenance(enabled);
+  log.info(`Maintenance mode ${enabled ? 'enabled' : 'disabled'} by ${req.ip}.`);
+
+  res.json({enabled});
+});
+
+admin.get('/logins', (req, res) => {
+  res.json(recentJoins());
+});
+
+admin.get('/rooms', (req, res) => {
+  res.json(knownRooms());
+});
+
+admin.get('/sessions', (req, res) => {
+  res.json(pendingLogins());
+});
+
+// terminating rooms and kicking participants requires the http modules of prosody
+if (PROSODY_URL) {
+  admin.delete('/rooms/:room', catchErrors(async (req, res) => {
+    const {room} = req.params;
+
+    try {
+      await endMeeting(room);
+      log.info(`[audit] ended meeting ${room} by ${req.ip}`, {room});
+      res.status(204).end();
+    }
+    catch (error) {
+      prosodyError(res, error);
+    }
+  }));
+
+  admin.delete('/rooms/:room/participants/:participant', catchErrors(async (req, res) => {
+    const {room, participant} = req.params;
+
+    try {
+      await kickParticipant(room, participant);
+      log.info(`[audit] kicked participant ${participant} from ${room} by ${req.ip}`, {room});
+      res.status(204).end();
+    }
+    catch (error) {
+      prosodyError(res, error);
+    }
+  }));
+}
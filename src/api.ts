@@ -0,0 +1,168 @@
+import * as express from 'express';
+import {createHash, timingSafeEqual} from 'crypto';
+import {API_CLIENTS, BODY_LIMIT, SHORT_LINK_TTL, TOKEN_EXCHANGE} from './config';
+import {jitsiBackend, roomUrl, sign} from './jitsi';
+import {withoutCompression} from './compression';
+import {tokenIssued} from './metrics';
+import {audit, auditToken} from './audit';
+import * as log from './log';
+import {createLink, revokeLink} from './links';
+import {recordJoin} from './logins';
+import {verifyAccessToken} from './access-token';
+
+export const api = express.Router();
+
+api.use(express.json({limit: BODY_LIMIT}));
+
+function digest(value: string): Buffer {
+  return createHash('sha256').update(value).digest();
+}
+
+function authenticate(req: express.Request): string | undefined {
+  const [scheme, key] = req.header('authorization')?.split(' ') ?? [];
+
+  if (scheme?.toLowerCase() !== 'bearer' || !key) {
+    return undefined;
+  }
+
+  const actual = digest(key);
+
+  return Object.entries(API_CLIENTS)
+    .find(([, client]) => timingSafeEqual(digest(client.key), actual))?.[0];
+}
+
+function isRoomAllowed(patterns: string[], room: string): boolean {
+  return patterns.some(pattern => pattern.endsWith('*')
+    ? room.startsWith(pattern.slice(0, -1))
+    : room === pattern);
+}
+
+/**
+ * Exchanges an access token of the identity provider (sent instead of an api
+ * key) for a token of the requested room.
+ */
+async function exchange(req: express.Request, res: express.Response) {
+  const [, accessToken] = req.header('authorization')?.split(' ') ?? [];
+  const {room} = req.body ?? {};
+
+  if (typeof room !== 'string' || !room) {
+    res.status(400).json({error: 'invalid_request'});
+    return;
+  }
+
+  const user = await verifyAccessToken(accessToken);
+
+  if (!user) {
+    log.warn(`[audit] rejected token exchange from ${req.ip}: invalid access token`, {errorKind: 'invalid_token'});
+    audit(req, 'access_denied', {flow: 'exchange', room, reason: 'invalid_token'});
+    res.status(401).json({error: 'invalid_token'});
+    return;
+  }
+
+  const backend = jitsiBackend(room, req.hostname);
+  const token = sign(user, room, backend);
+  tokenIssued('exchange', room, room);
+  auditToken(req, user, room, 'exchange', token);
+  recordJoin(user.id, user.name, room, 'exchange');
+  log.info(`[audit] exchanged access token of ${user.id} for room ${room}`, {room});
+
+  withoutCompression(res);
+  res.json({jwt: token, url: roomUrl(room, token, backend)});
+}
+
+/**
+ * Lets trusted backend services (recorders, bots, ...) mint tokens for the
+ * rooms they are allowed to join. Every attempt is written to the audit log.
+ * With TOKEN_EXCHANGE, bearer tokens which aren't api keys are treated as
+ * access tokens of the identity provider.
+ */
+api.post('/token', (req, res, next) => {
+  const clientId = authenticate(req);
+
+  if (!clientId && TOKEN_EXCHANGE && /^bearer /i.test(req.header('authorization') ?? '')) {
+    exchange(req, res).catch(next);
+    return;
+  }
+
+  if (!clientId) {
+    log.warn(`[audit] rejected api token request from ${req.ip}: invalid api key`, {errorKind: 'invalid_client'});
+    audit(req, 'access_denied', {flow: 'api', reason: 'invalid_client'});
+    res.status(401).json({error: 'invalid_client'});
+    return;
+  }
+
+  const {room, name} = req.body ?? {};
+
+  if (typeof room !== 'string' || !room || (name !== undefined && typeof name !== 'string')) {
+    res.status(400).json({error: 'invalid_request'});
+    return;
+  }
+
+  if (!isRoomAllowed(API_CLIENTS[clientId].rooms, room)) {
+    log.warn(`[audit] rejected api token request of ${clientId} for room ${room}: room not allowed`, {client: clientId, room, errorKind: 'room_not_allowed'});
+    audit(req, 'access_denied', {flow: 'api', subject: clientId, room, reason: 'room_not_allowed'});
+    res.status(403).json({error: 'room_not_allowed'});
+    return;
+  }
+
+  const backend = jitsiBackend(room, req.hostname);
+  const user = {id: clientId, name: name ?? clientId, email: ''};
+  const token = sign(user, room, backend);
+  tokenIssued('api', room, room);
+  auditToken(req, user, room, 'api', token);
+  recordJoin(clientId, name ?? clientId, room, 'api');
+  log.info(`[audit] issued api token to ${clientId} for room ${room}`, {client: clientId, room});
+
+  withoutCompression(res);
+  res.json({jwt: token, url: roomUrl(room, token, backend)});
+});
+
+/**
+ * Mints a short link (/j/ab23cd) to a room the client is allowed to join,
+ * optionally with query parameters and a shorter lifetime (ttl in seconds).
+ */
+api.post('/links', (req, res) => {
+  const clientId = authenticate(req);
+
+  if (!clientId) {
+    res.status(401).json({error: 'invalid_client'});
+    return;
+  }
+
+  const {room, params, ttl} = req.body ?? {};
+
+  if (typeof room !== 'string' || !room
+    || (params !== undefined && (typeof params !== 'object' || params === null || Object.values(params).some(value => typeof value !== 'string')))
+    || (ttl !== undefined && (typeof ttl !== 'number' || ttl <= 0))) {
+    res.status(400).json({error: 'invalid_request'});
+    return;
+  }
+
+  if (!isRoomAllowed(API_CLIENTS[clientId].rooms, room)) {
+    res.status(403).json({error: 'room_not_allowed'});
+    return;
+  }
+
+  const expires = Date.now() + Math.min(ttl ?? SHORT_LINK_TTL, SHORT_LINK_TTL) * 1000;
+  const link = createLink({room, query: new URLSearchParams(params ?? {}).toString(), client: clientId, expires});
+  log.info(`[audit] created short link ${link.slug} of ${clientId} for room ${room}`, {client: clientId, room});
+
+  res.status(201).json({...link, expires_at: new Date(expires).toISOString()});
+});
+
+api.delete('/links/:slug', (req, res) => {
+  const clientId = authenticate(req);
+
+  if (!clientId) {
+    res.status(401).json({error: 'invalid_client'});
+    return;
+  }
+
+  if (!revokeLink(req.params.slug, clientId)) {
+    res.status(404).json({error: 'not_found'});
+    return;
+  }
+
+  log.info(`[audit] revoked short link ${req.params.slug} of ${clientId}`, {client: clientId});
+  res.status(204).end();
+});
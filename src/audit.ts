@@ -0,0 +1,108 @@
+import * as express from 'express';
+import {createHmac} from 'crypto';
+import {createWriteStream} from 'fs';
+import {decode} from 'jsonwebtoken';
+import {AUDIT_LOG, AUDIT_WEBHOOK_SECRET, AUDIT_WEBHOOK_URL} from './config';
+import {locate} from './geoip';
+import {http} from './http-client';
+import {JitsiUser} from './jitsi';
+import * as log from './log';
+
+const WEBHOOK_INTERVAL = 1000;
+const WEBHOOK_BATCH_SIZE = 100;
+// events are dropped (oldest first) while the webhook is unreachable for too long
+const WEBHOOK_QUEUE_LIMIT = 10000;
+
+function open(): NodeJS.WritableStream | undefined {
+  if (!AUDIT_LOG) {
+    return undefined;
+  }
+
+  if (AUDIT_LOG === 'stdout') {
+    return process.stdout;
+  }
+
+  const stream = createWriteStream(AUDIT_LOG, {flags: 'a'});
+  stream.on('error', error => process.stderr.write(`Writing the audit log failed: ${error.message}\n`));
+
+  return stream;
+}
+
+const stream = open();
+const queue: Record<string, unknown>[] = [];
+let sending = false;
+
+/**
+ * Posts the queued events as JSON array, signed with AUDIT_WEBHOOK_SECRET
+ * (X-Signature: sha256=<hex hmac of the body>) if set. Failed batches are
+ * sent again with the next interval.
+ */
+async function sendEvents() {
+  if (sending || !queue.length) {
+    return;
+  }
+
+  sending = true;
+  const batch = queue.splice(0, WEBHOOK_BATCH_SIZE);
+  const body = JSON.stringify(batch);
+
+  try {
+    await http.post(AUDIT_WEBHOOK_URL as string, {
+      body,
+      headers: {
+        'content-type': 'application/json',
+        ...(AUDIT_WEBHOOK_SECRET ? {'x-signature': `sha256=${createHmac('sha256', AUDIT_WEBHOOK_SECRET.reveal()).update(body).digest('hex')}`} : {}),
+      },
+    });
+  }
+  catch (error) {
+    log.warn(`Sending ${batch.length} audit events to the webhook failed: ${error.message}`, {errorKind: 'audit_webhook'});
+    queue.unshift(...batch);
+  }
+  finally {
+    sending = false;
+  }
+}
+
+if (AUDIT_WEBHOOK_URL) {
+  setInterval(sendEvents, WEBHOOK_INTERVAL).unref();
+}
+
+/**
+ * Writes an event (token_issued, login_completed, login_failed or
+ * access_denied) to AUDIT_LOG, one JSON object per line, and queues it for
+ * AUDIT_WEBHOOK_URL.
+ */
+export function audit(req: express.Request, event: string, fields: Record<string, unknown> = {}) {
+  const {country, asn, asOrganization} = locate(req.ip);
+  const entry = {time: new Date().toISOString(), event, ...fields, ip: req.ip, country, asn, as_org: asOrganization, request_id: req.id};
+
+  stream?.write(`${JSON.stringify(entry)}\n`);
+
+  if (AUDIT_WEBHOOK_URL) {
+    queue.push(entry);
+
+    if (queue.length > WEBHOOK_QUEUE_LIMIT) {
+      queue.splice(0, queue.length - WEBHOOK_QUEUE_LIMIT);
+      log.warn('Dropped audit events, the webhook is not reachable.', {errorKind: 'audit_webhook'});
+    }
+  }
+}
+
+/**
+ * Records an issued token. Tokens without expiry (JITSI_TOKEN_LIFETIME=0)
+ * are written with expires null.
+ */
+export function auditToken(req: express.Request, user: JitsiUser, room: string | undefined, flow: string, token: string) {
+  const claims = decode(token, {json: true});
+
+  audit(req, 'token_issued', {
+    subject: user.id,
+    room: room || null,
+    allowed_room: claims?.room ?? null,
+    moderator: user.moderator ?? null,
+    visitor: !!user.visitor,
+    flow,
+    expires: typeof claims?.exp === 'number' ? new Date(claims.exp * 1000).toISOString() : null,
+  });
+}
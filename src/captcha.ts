@@ -0,0 +1,75 @@
+import * as express from 'express';
+import {CAPTCHA_PROVIDER, CAPTCHA_SECRET, CAPTCHA_SITE_KEY} from './config';
+import {escapeHtml} from './html';
+import {http} from './http-client';
+import * as log from './log';
+
+interface CaptchaProvider {
+  script: string;
+  widget: string;
+  field: string;
+  verify: string;
+}
+
+const PROVIDERS: Record<string, CaptchaProvider> = {
+  hcaptcha: {
+    script: 'https://js.hcaptcha.com/1/api.js',
+    widget: 'h-captcha',
+    field: 'h-captcha-response',
+    verify: 'https://api.hcaptcha.com/siteverify',
+  },
+  turnstile: {
+    script: 'https://challenges.cloudflare.com/turnstile/v0/api.js',
+    widget: 'cf-turnstile',
+    field: 'cf-turnstile-response',
+    verify: 'https://challenges.cloudflare.com/turnstile/v0/siteverify',
+  },
+};
+
+const provider = CAPTCHA_PROVIDER ? PROVIDERS[CAPTCHA_PROVIDER] : undefined;
+
+/**
+ * The widget of CAPTCHA_PROVIDER, placed inside a form. It adds the response
+ * as hidden field when solved.
+ */
+export function captchaWidget(): string {
+  if (!provider) {
+    return '';
+  }
+
+  return `<script src="${provider.script}" async defer></script>
+      <div class="${provider.widget}" data-sitekey="${escapeHtml(CAPTCHA_SITE_KEY as string)}"></div>`;
+}
+
+/**
+ * Verifies the response of the widget with the provider, the captcha is
+ * considered unsolved if the provider isn't reachable.
+ */
+export async function isCaptchaSolved(req: express.Request): Promise<boolean> {
+  if (!provider) {
+    return true;
+  }
+
+  const response = req.body?.[provider.field];
+
+  if (typeof response !== 'string' || !response) {
+    return false;
+  }
+
+  try {
+    const {success} = await http.post(provider.verify, {
+      form: {
+        secret: CAPTCHA_SECRET?.reveal(),
+        response,
+        remoteip: req.ip,
+        ...(CAPTCHA_PROVIDER === 'hcaptcha' ? {sitekey: CAPTCHA_SITE_KEY} : {}),
+      },
+    }).json<{success?: boolean}>();
+
+    return success === true;
+  }
+  catch (error) {
+    log.warn(`Verifying the captcha failed: ${error.message}`, {errorKind: 'captcha'});
+    return false;
+  }
+}
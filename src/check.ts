@@ -0,0 +1,48 @@
+import {existsSync} from 'fs';
+import {CLI} from './cli';
+import {CONFIG_PROBLEMS, HTTP_REDIRECT_ADDR, LISTEN_ADDR, STATIC_DIR, TLS_CERT, TLS_KEY} from './config';
+import {parseAddresses} from './listen';
+import {getClient, oidcProviders} from './oidc';
+
+/**
+ * Validates the configuration (and optionally performs the discovery of all
+ * OpenID Connect providers), prints all problems and exits non-zero if there
+ * are any.
+ */
+export async function check() {
+  const problems = [...CONFIG_PROBLEMS];
+
+  for (const [name, addresses] of [['LISTEN_ADDR', LISTEN_ADDR], ['HTTP_REDIRECT_ADDR', HTTP_REDIRECT_ADDR]]) {
+    try {
+      parseAddresses(addresses ?? '');
+    }
+    catch (error) {
+      problems.push(`${name}: ${error.message}`);
+    }
+  }
+
+  for (const [name, path] of [['TLS_CERT', TLS_CERT], ['TLS_KEY', TLS_KEY], ['STATIC_DIR', STATIC_DIR]]) {
+    if (path && !existsSync(path)) {
+      problems.push(`${name}: ${path} does not exist.`);
+    }
+  }
+
+  if (CLI.options.DISCOVER && !CONFIG_PROBLEMS.length) {
+    for (const provider of oidcProviders()) {
+      try {
+        await getClient(provider);
+      }
+      catch (error) {
+        problems.push(`Discovery of provider ${provider} failed: ${error.message}`);
+      }
+    }
+  }
+
+  if (problems.length) {
+    problems.forEach(problem => console.error(problem));
+    process.exit(1);
+  }
+
+  console.log('Configuration is valid.');
+  process.exit(0);
+}
@@ -0,0 +1,89 @@
+import {VERSION} from './version';
+
+const COMMANDS = ['serve', 'check', 'print-config'];
+
+const USAGE = `Usage: jitsi-openid [command] [options]
+
+Commands:
+  serve                 Start the server (default)
+  check                 Validate the configuration and exit, --discover also performs the discovery
+  print-config          Print an annotated example configuration, --format env (default) or toml
+
+Options:
+  --config <file>       Load a TOML or JSON config file (CONFIG_FILE)
+  --profile <name>      Apply the dev, staging or prod profile and its config file (PROFILE)
+  --<name> <value>      Set any environment variable, e.g. --jitsi-url https://meet.example.com (JITSI_URL)
+  --check               Same as the check command
+  --help                Show this help
+  --version             Show the version
+`;
+
+export interface Cli {
+  command: string;
+  options: Record<string, string>;
+}
+
+function fail(message: string): never {
+  console.error(`${message}\n\n${USAGE}`);
+  process.exit(2);
+}
+
+export function parseArgs(args: string[]): Cli {
+  const cli: Cli = {command: 'serve', options: {}};
+  let index = 0;
+
+  if (args[0] && !args[0].startsWith('-')) {
+    cli.command = args[0];
+    index = 1;
+  }
+
+  for (; index < args.length; index++) {
+    const arg = args[index];
+
+    if (!arg.startsWith('--')) {
+      fail(`Unexpected argument ${arg}.`);
+    }
+
+    const [flag, inline] = arg.slice(2).split(/=(.*)/s, 2);
+    const name = flag === 'config' ? 'CONFIG_FILE' : flag.toUpperCase().replace(/-/g, '_');
+
+    if (inline !== undefined) {
+      cli.options[name] = inline;
+    }
+    else if (args[index + 1] !== undefined && !args[index + 1].startsWith('--')) {
+      cli.options[name] = args[++index];
+    }
+    else {
+      cli.options[name] = 'true';
+    }
+  }
+
+  if (cli.options.CHECK) {
+    cli.command = 'check';
+    delete cli.options.CHECK;
+  }
+
+  return cli;
+}
+
+/**
+ * Parses the command line, flags take precedence over the environment and the
+ * config file.
+ */
+export const CLI = parseArgs(process.argv.slice(2));
+
+if (CLI.options.HELP) {
+  console.log(USAGE);
+  process.exit(0);
+}
+
+if (CLI.options.VERSION) {
+  console.log(`jitsi-openid ${VERSION.version} (${VERSION.commit}, built ${VERSION.buildTimestamp})`);
+  process.exit(0);
+}
+
+if (!COMMANDS.includes(CLI.command)) {
+  fail(`Unknown command ${CLI.command}.`);
+}
+
+Object.assign(process.env, CLI.options);
@@ -0,0 +1,53 @@
+import * as express from 'express';
+import {brotliCompressSync, gzipSync} from 'zlib';
+import {COMPRESSION} from './config';
+
+// smaller responses don't benefit from compression
+const THRESHOLD = 1024;
+
+/**
+ * Keeps a response carrying a token uncompressed. Compressed together with
+ * input reflected from the request (e.g. the room name), the token could be
+ * recovered from the response sizes (BREACH).
+ */
+export function withoutCompression(res: express.Response) {
+  res.set('Cache-Control', 'no-store, no-transform');
+}
+
+/**
+ * Compresses the (small, generated) pages sent using res.send with brotli or
+ * gzip, redirects, streamed responses and responses marked with no-transform
+ * are left untouched.
+ */
+export function compression(req: express.Request, res: express.Response, next: express.NextFunction) {
+  if (!COMPRESSION) {
+    next();
+    return;
+  }
+
+  const send = res.send.bind(res);
+
+  res.send = function (body?: unknown) {
+    res.vary('Accept-Encoding');
+
+    if (typeof body !== 'string' || Buffer.byteLength(body) < THRESHOLD || res.get('Content-Encoding')
+      || /\bno-transform\b/.test(res.get('Cache-Control') ?? '')) {
+      return send(body);
+    }
+
+    const encoding = req.acceptsEncodings('br', 'gzip');
+
+    if (encoding !== 'br' && encoding !== 'gzip') {
+      return send(body);
+    }
+
+    if (!res.get('Content-Type')) {
+      res.type('html');
+    }
+
+    res.set('Content-Encoding', encoding);
+    return send(encoding === 'br' ? brotliCompressSync(body) : gzipSync(body));
+  } as typeof res.send;
+
+  next();
+}
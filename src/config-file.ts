@@ -0,0 +1,321 @@
+import {existsSync, readFileSync} from 'fs';
+import {extname} from 'path';
+
+// defaults of the built-in profiles, overridden by config files and the environment
+const PROFILES: Record<string, Record<string, string>> = {
+  dev: {LOG_LEVEL: 'debug', SESSION_COOKIE_SECURE: 'false'},
+  staging: {LOG_FORMAT: 'json', LOG_LEVEL: 'debug', SESSION_COOKIE_SECURE: 'true'},
+  prod: {LOG_FORMAT: 'json', LOG_LEVEL: 'info', SESSION_COOKIE_SECURE: 'true', HTTP_LOG: 'errors'},
+};
+
+// variables set from files, which are replaced when the files are loaded again
+const loaded = new Set<string>();
+
+// values of secret manager references, resolved before the config is loaded
+const references = new Map<string, string>();
+
+export function addResolvedReference(reference: string, value: string) {
+  references.set(reference, value);
+}
+
+function assign(name: string, value: string) {
+  if (process.env[name] === undefined || loaded.has(name)) {
+    process.env[name] = references.get(value) ?? value;
+    loaded.add(name);
+  }
+}
+
+type Value = string | number | boolean | Value[] | {[key: string]: Value};
+
+/**
+ * Splits the entries of an array at commas, which are not part of a string or
+ * a nested array.
+ */
+function splitArray(raw: string, line: number): string[] {
+  const entries: string[] = [];
+  let quote: string | undefined;
+  let depth = 0;
+  let start = 0;
+
+  for (let index = 0; index < raw.length; index++) {
+    const char = raw[index];
+
+    if (quote) {
+      if (char === '\\' && quote === '"') {
+        index++;
+      }
+      else if (char === quote) {
+        quote = undefined;
+      }
+    }
+    else if (char === '"' || char === '\'') {
+      quote = char;
+    }
+    else if (char === '[') {
+      depth++;
+    }
+    else if (char === ']') {
+      depth--;
+    }
+    else if (char === ',' && depth === 0) {
+      entries.push(raw.slice(start, index));
+      start = index + 1;
+    }
+  }
+
+  if (quote || depth !== 0) {
+    throw new Error(`Unterminated string or array on line ${line}.`);
+  }
+
+  entries.push(raw.slice(start));
+  return entries.map(entry => entry.trim()).filter(Boolean);
+}
+
+function parseTomlValue(raw: string, line: number): Value {
+  if (raw.startsWith('"')) {
+    try {
+      return JSON.parse(raw);
+    }
+    catch (error) {
+      throw new Error(`Invalid string on line ${line}: ${raw}`);
+    }
+  }
+
+  if (raw.startsWith('\'') && raw.endsWith('\'')) {
+    return raw.slice(1, -1);
+  }
+
+  if (raw === 'true' || raw === 'false') {
+    return raw === 'true';
+  }
+
+  if (raw.startsWith('[') && raw.endsWith(']')) {
+    return splitArray(raw.slice(1, -1), line).map(entry => parseTomlValue(entry, line));
+  }
+
+  if (raw.startsWith('{')) {
+    throw new Error(`Inline tables are not supported (line ${line}), use a [table] or a JSON config file instead.`);
+  }
+
+  const number = Number(raw.replace(/_/g, ''));
+
+  if (raw && Number.isFinite(number)) {
+    return number;
+  }
+
+  throw new Error(`Unsupported value on line ${line}: ${raw}`);
+}
+
+/**
+ * Parses the subset of TOML needed for configuration: tables, strings,
+ * numbers, booleans and single line arrays. Inline tables, arrays of tables
+ * and multi-line values are rejected.
+ */
+function parseToml(content: string): Record<string, Value> {
+  const result: Record<string, Value> = {};
+  let table = result;
+
+  content.split(/\r?\n/).forEach((text, index) => {
+    const line = text.replace(/^((?:[^#"']|"(?:[^"\\]|\\.)*"|'[^']*')*)#.*$/, '$1').trim();
+
+    if (!line) {
+      return;
+    }
+
+    const header = line.match(/^\[([\w\-]+(?:\.[\w\-]+)*)]$/);
+
+    if (header) {
+      table = result;
+
+      for (const key of header[1].split('.')) {
+        table[key] = table[key] ?? {};
+        table = table[key] as Record<string, Value>;
+      }
+
+      return;
+    }
+
+    const entry = line.match(/^([\w\-]+)\s*=\s*(.+)$/);
+
+    if (!entry) {
+      throw new Error(`Invalid line ${index + 1}: ${text}`);
+    }
+
+    table[entry[1]] = parseTomlValue(entry[2], index + 1);
+  });
+
+  return result;
+}
+
+/**
+ * Replaces ${NAME} and ${NAME:-default} in strings with environment
+ * variables, $${ escapes the interpolation.
+ */
+function interpolate(value: Value, path: string): Value {
+  if (Array.isArray(value)) {
+    return value.map(entry => interpolate(entry, path));
+  }
+
+  if (typeof value === 'object') {
+    return Object.fromEntries(Object.entries(value).map(([key, entry]) => [key, interpolate(entry, `${path}${key}.`)]));
+  }
+
+  if (typeof value !== 'string') {
+    return value;
+  }
+
+  return value.replace(/\$(\$?)\{(\w+)(?::-([^}]*))?}/g, (match, escaped, name, fallback) => {
+    if (escaped) {
+      return match.slice(1);
+    }
+
+    const replacement = process.env[name] ?? fallback;
+
+    if (replacement === undefined) {
+      throw new Error(`Environment variable ${name} used by ${path.slice(0, -1)} is not set.`);
+    }
+
+    return replacement;
+  });
+}
+
+/**
+ * Maps nested keys to environment variable names ([jitsi] url → JITSI_URL),
+ * objects below known JSON variables (e.g. PROVIDERS) are kept as JSON.
+ */
+function flatten(values: Record<string, Value>, prefix: string, jsonKeys: Set<string>, result: Record<string, string>) {
+  for (const [key, value] of Object.entries(values)) {
+    const name = `${prefix}${key}`.toUpperCase().replace(/[.\-]/g, '_');
+
+    if (Array.isArray(value)) {
+      result[name] = value.every(entry => typeof entry !== 'object') ? value.join(',') : JSON.stringify(value);
+    }
+    else if (typeof value === 'object') {
+      if (jsonKeys.has(name)) {
+        result[name] = JSON.stringify(value);
+      }
+      else {
+        flatten(value, `${name}_`, jsonKeys, result);
+      }
+    }
+    else {
+      result[name] = String(value);
+    }
+  }
+
+  return result;
+}
+
+function readConfigFile(path: string, jsonKeys: string[]): Record<string, string> {
+  let values: Record<string, Value>;
+
+  try {
+    const content = readFileSync(path, 'utf8');
+    values = extname(path) === '.json' ? JSON.parse(content) : parseToml(content);
+  }
+  catch (error) {
+    throw new Error(`Unable to read config file ${path}: ${error.message}`);
+  }
+
+  try {
+    values = interpolate(values, '') as Record<string, Value>;
+  }
+  catch (error) {
+    throw new Error(`Unable to interpolate config file ${path}: ${error.message}`);
+  }
+
+  return flatten(values, '', new Set(jsonKeys), {});
+}
+
+// /etc/jitsi-openid.toml → /etc/jitsi-openid.prod.toml
+function profilePath(path: string, profile: string): string {
+  const extension = extname(path);
+  return `${path.slice(0, path.length - extension.length)}.${profile}${extension}`;
+}
+
+/**
+ * Loads the config file passed using --config (or CONFIG_FILE) into the
+ * environment, layered with the file and the defaults of the profile selected
+ * using PROFILE. Variables already set in the environment take precedence,
+ * loading the file again replaces the values of its previous load. Returns
+ * the names set by the files.
+ */
+export function loadConfigFile(jsonKeys: string[]): string[] {
+  const path = process.env.CONFIG_FILE;
+  const profile = process.env.PROFILE;
+  const profileFile = path && profile ? profilePath(path, profile) : undefined;
+
+  if (profile && !PROFILES[profile] && !(profileFile && existsSync(profileFile))) {
+    throw new Error(`Unknown PROFILE ${profile}, expected ${Object.keys(PROFILES).join(', ')} or an existing ${profileFile ?? 'profile config file'}.`);
+  }
+
+  const files = {
+    ...(path ? readConfigFile(path, jsonKeys) : {}),
+    ...(profileFile && existsSync(profileFile) ? readConfigFile(profileFile, jsonKeys) : {}),
+  };
+
+  for (const [name, value] of Object.entries({...(profile ? PROFILES[profile] : {}), ...files})) {
+    assign(name, value);
+  }
+
+  return Object.keys(files);
+}
+
+/**
+ * The values the config file, the profile and NAME_FILE would set for the
+ * given names, without loading them, so secret manager references can be
+ * resolved first. Unreadable files are reported when they are loaded.
+ */
+export function pendingFileValues(names: string[]): string[] {
+  const path = process.env.CONFIG_FILE;
+  const profile = process.env.PROFILE;
+  const profileFile = path && profile ? profilePath(path, profile) : undefined;
+  let files: Record<string, string> = {};
+
+  try {
+    files = {
+      ...(path ? readConfigFile(path, []) : {}),
+      ...(profileFile && existsSync(profileFile) ? readConfigFile(profileFile, []) : {}),
+    };
+  }
+  catch (error) {
+    return [];
+  }
+
+  return names
+    .filter(name => process.env[name] === undefined)
+    .flatMap(name => {
+      const file = process.env[`${name}_FILE`];
+      const values: (string | undefined)[] = [files[name]];
+
+      try {
+        values.push(file ? readFileSync(file, 'utf8').replace(/\r?\n$/, '') : undefined);
+      }
+      catch (error) {
+        // reported by loadFileVariables
+      }
+
+      return values.filter((value): value is string => value !== undefined);
+    });
+}
+
+/**
+ * Reads NAME from the file referenced by NAME_FILE (e.g. a docker or
+ * kubernetes secret), unless NAME itself is set.
+ */
+export function loadFileVariables(names: string[]) {
+  for (const name of names) {
+    const path = process.env[`${name}_FILE`];
+
+    if (!path) {
+      continue;
+    }
+
+    try {
+      assign(name, readFileSync(path, 'utf8').replace(/\r?\n$/, ''));
+    }
+    catch (error) {
+      throw new Error(`Unable to read ${name}_FILE ${path}: ${error.message}`);
+    }
+  }
+}
@@ -0,0 +1,87 @@
+import * as express from 'express';
+import {createHmac, timingSafeEqual} from 'crypto';
+import {SECRET, SESSION_COOKIE_DOMAIN, SESSION_COOKIE_PATH, SESSION_COOKIE_SECURE} from './config';
+
+function signature(value: string): string {
+  return createHmac('sha256', SECRET).update(value).digest('base64url');
+}
+
+/**
+ * Serializes a value into a tamper proof (but readable) cookie value.
+ */
+export function seal(value: unknown): string {
+  const payload = Buffer.from(JSON.stringify(value)).toString('base64url');
+  return `${payload}.${signature(payload)}`;
+}
+
+export function unseal<T>(sealed: string | undefined): T | undefined {
+  const [payload, actual] = sealed?.split('.') ?? [];
+
+  if (!payload || !actual) {
+    return undefined;
+  }
+
+  const expected = Buffer.from(signature(payload));
+
+  if (expected.length !== actual.length || !timingSafeEqual(expected, Buffer.from(actual))) {
+    return undefined;
+  }
+
+  try {
+    return JSON.parse(Buffer.from(payload, 'base64url').toString());
+  }
+  catch (error) {
+    return undefined;
+  }
+}
+
+/**
+ * Token for forms changing state, bound to the user and the purpose (e.g.
+ * invite:<sub>:<room>). Other sites can't forge it, even if the session
+ * cookie is sent along (SESSION_COOKIE_SAME_SITE=none).
+ */
+export function formToken(scope: string): string {
+  return signature(`form:${scope}`);
+}
+
+export function isFormToken(scope: string, token: unknown): boolean {
+  const expected = Buffer.from(formToken(scope));
+  return typeof token === 'string' && expected.length === Buffer.byteLength(token) && timingSafeEqual(expected, Buffer.from(token));
+}
+
+/**
+ * Options for short lived cookies of the login flow. They are secure if the
+ * session cookie is or the request used https, which honors X-Forwarded-Proto
+ * of trusted proxies.
+ */
+export function transientCookie(req: express.Request): express.CookieOptions {
+  return {
+    httpOnly: true,
+    sameSite: 'lax',
+    secure: SESSION_COOKIE_SECURE || req.secure,
+    path: SESSION_COOKIE_PATH,
+    domain: SESSION_COOKIE_DOMAIN,
+    maxAge: 10 * 60 * 1000,
+  };
+}
+
+/**
+ * Reads a cookie of the request, a value with malformed percent encoding is
+ * treated as absent.
+ */
+export function getCookie(req: express.Request, name: string): string | undefined {
+  for (const cookie of req.header('cookie')?.split(';') ?? []) {
+    const index = cookie.indexOf('=');
+
+    if (index !== -1 && cookie.slice(0, index).trim() === name) {
+      try {
+        return decodeURIComponent(cookie.slice(index + 1).trim());
+      }
+      catch (error) {
+        return undefined;
+      }
+    }
+  }
+
+  return undefined;
+}
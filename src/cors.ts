@@ -0,0 +1,40 @@
+import * as express from 'express';
+import {CORS_METHODS, CORS_ORIGINS} from './config';
+
+function allowedOrigin(origin: string | undefined): string | undefined {
+  if (!origin) {
+    return undefined;
+  }
+
+  if (CORS_ORIGINS.includes('*')) {
+    return '*';
+  }
+
+  return CORS_ORIGINS.includes(origin) ? origin : undefined;
+}
+
+/**
+ * Allows browser based frontends of the configured origins to call the JSON
+ * endpoints. Credentials (cookies) are never allowed cross origin.
+ */
+export function cors(req: express.Request, res: express.Response, next: express.NextFunction) {
+  const origin = allowedOrigin(req.header('origin'));
+  res.vary('Origin');
+
+  if (!origin) {
+    next();
+    return;
+  }
+
+  res.set('Access-Control-Allow-Origin', origin);
+
+  if (req.method === 'OPTIONS' && req.header('access-control-request-method')) {
+    res.set('Access-Control-Allow-Methods', CORS_METHODS.join(', '));
+    res.set('Access-Control-Allow-Headers', 'Authorization, Content-Type');
+    res.set('Access-Control-Max-Age', '600');
+    res.sendStatus(204);
+    return;
+  }
+
+  next();
+}
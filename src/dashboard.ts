@@ -0,0 +1,60 @@
+import * as express from 'express';
+import {DASHBOARD_ROLE, DASHBOARD_ROLE_CLAIM} from './config';
+import {claimValues} from './directory';
+import {errorPage} from './errors';
+import {audit} from './audit';
+import {escapeHtml, page} from './html';
+import {knownRooms, pendingLogins, recentJoins} from './logins';
+
+function time(timestamp: number): string {
+  return escapeHtml(new Date(timestamp).toISOString().replace('T', ' ').slice(0, 19));
+}
+
+function table(headers: string[], rows: string[][], empty: string): string {
+  if (!rows.length) {
+    return `<p>${escapeHtml(empty)}</p>`;
+  }
+
+  return `<table>
+    <thead><tr>${headers.map(header => `<th>${escapeHtml(header)}</th>`).join('')}</tr></thead>
+    <tbody>${rows.map(row => `<tr>${row.map(cell => `<td>${cell}</td>`).join('')}</tr>`).join('')}</tbody>
+  </table>`;
+}
+
+/**
+ * Read only view of the data of the admin api for users with DASHBOARD_ROLE,
+ * so moderators don't need the admin token.
+ */
+export function dashboard(baseURL: string): express.RequestHandler {
+  return (req, res) => {
+    if (!claimValues(req.oidc.user, DASHBOARD_ROLE_CLAIM).includes(DASHBOARD_ROLE as string)) {
+      audit(req, 'access_denied', {flow: 'dashboard', subject: req.oidc.user?.sub, reason: 'missing_role'});
+      errorPage(req, res, 403);
+      return;
+    }
+
+    const joins = recentJoins().map(({time: joined, name, user, room, flow}) => [
+      time(joined),
+      escapeHtml(name || user),
+      `<a href="${escapeHtml(`${baseURL}/room/${encodeURIComponent(room)}`)}">${escapeHtml(room)}</a>`,
+      escapeHtml(flow),
+    ]);
+
+    const rooms = knownRooms().map(({room, joins: count, lastJoin}) => [
+      `<a href="${escapeHtml(`${baseURL}/room/${encodeURIComponent(room)}`)}">${escapeHtml(room)}</a>`,
+      String(count),
+      time(lastJoin),
+    ]);
+
+    const sessions = pendingLogins().map(({provider, started}) => [escapeHtml(provider), time(started)]);
+
+    res.send(page('Dashboard', `
+      <h2>Recent logins</h2>
+      ${table(['Time (UTC)', 'User', 'Room', 'Flow'], joins, 'Nobody joined a room since the last restart.')}
+      <h2>Rooms</h2>
+      ${table(['Room', 'Joins', 'Last join (UTC)'], rooms, 'No rooms were joined in the last 24 hours.')}
+      <h2>Pending logins</h2>
+      ${table(['Provider', 'Started (UTC)'], sessions, 'No logins are waiting for the identity provider.')}
+    `));
+  };
+}
@@ -0,0 +1,105 @@
+import * as express from 'express';
+import {errors} from 'openid-client';
+import {BODY_LIMIT} from './config';
+import {getClient} from './oidc';
+import {jitsiBackend, roomUrl, sign} from './jitsi';
+import {withoutCompression} from './compression';
+import {tokenIssued} from './metrics';
+import {auditToken} from './audit';
+import {recordJoin} from './logins';
+import {catchErrors} from './errors';
+import {http} from './http-client';
+
+const DEVICE_CODE_GRANT = 'urn:ietf:params:oauth:grant-type:device_code';
+const DEFAULT_INTERVAL = 5;
+
+export const device = express.Router();
+
+device.use(express.urlencoded({extended: false, limit: BODY_LIMIT}));
+device.use(express.json({limit: BODY_LIMIT}));
+
+interface DeviceAuthorization {
+  device_code: string;
+  user_code: string;
+  verification_uri: string;
+  verification_uri_complete?: string;
+  expires_in: number;
+  interval?: number;
+}
+
+function basicAuth(clientId: string, clientSecret: string): string {
+  const credentials = `${encodeURIComponent(clientId)}:${encodeURIComponent(clientSecret)}`;
+  return `Basic ${Buffer.from(credentials).toString('base64')}`;
+}
+
+/**
+ * Starts a device authorization (RFC 8628). The returned user code has to be
+ * confirmed by the user at the verification uri of the identity provider.
+ * The request is sent directly, as the handle of openid-client hides the
+ * polling interval of the identity provider.
+ */
+device.post('/code', catchErrors(async (req, res) => {
+  const client = await getClient();
+  const endpoint = client.issuer.metadata.device_authorization_endpoint;
+
+  if (!endpoint) {
+    throw new Error('The identity provider does not support the device authorization grant.');
+  }
+
+  const {client_id, client_secret} = client.metadata;
+  const authorization = await http.post(endpoint, {
+    headers: {accept: 'application/json', ...(client_secret ? {authorization: basicAuth(client_id, client_secret)} : {})},
+    form: {scope: 'openid profile email', ...(client_secret ? {} : {client_id})},
+  }).json<DeviceAuthorization>();
+
+  res.json({
+    device_code: authorization.device_code,
+    user_code: authorization.user_code,
+    verification_uri: authorization.verification_uri,
+    verification_uri_complete: authorization.verification_uri_complete,
+    expires_in: authorization.expires_in,
+    interval: authorization.interval ?? DEFAULT_INTERVAL,
+  });
+}));
+
+/**
+ * Polled by the device until the user confirmed the code. Errors of the
+ * identity provider (authorization_pending, slow_down, ...) are passed through.
+ */
+device.post('/token', catchErrors(async (req, res) => {
+  const {device_code, room} = req.body ?? {};
+
+  if (typeof device_code !== 'string' || (room !== undefined && typeof room !== 'string')) {
+    res.status(400).json({error: 'invalid_request'});
+    return;
+  }
+
+  try {
+    const client = await getClient();
+    const tokenSet = await client.grant({grant_type: DEVICE_CODE_GRANT, device_code});
+    const {sub, name, email} = await client.userinfo(tokenSet);
+
+    const backend = jitsiBackend(room ?? '', req.hostname);
+    const user = {id: sub, name: name ?? '', email: email ?? ''};
+    // a token of a device which didn't request a room is valid for any room
+    const allowedRoom = room || '*';
+    const token = sign(user, allowedRoom, backend);
+    tokenIssued('device', room, allowedRoom);
+    auditToken(req, user, room, 'device', token);
+
+    if (room) {
+      recordJoin(sub, name ?? '', room, 'device');
+    }
+
+    withoutCompression(res);
+    res.json({jwt: token, url: room ? roomUrl(room, token, backend) : undefined});
+  }
+  catch (error) {
+    if (error instanceof errors.OPError) {
+      res.status(400).json({error: error.error, error_description: error.error_description});
+      return;
+    }
+
+    throw error;
+  }
+}));
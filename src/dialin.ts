@@ -0,0 +1,46 @@
+import * as express from 'express';
+import {DIAL_IN_CONFERENCE_MAPPER, DIAL_IN_NUMBERS, PROSODY_MUC_DOMAIN} from './config';
+import {escapeHtml, page} from './html';
+import {http} from './http-client';
+import {translator} from './i18n';
+import * as log from './log';
+
+/**
+ * Resolves the PIN of a conference using the conference mapper of jigasi,
+ * which assigns a PIN to every conference.
+ */
+async function conferencePin(room: string): Promise<number | undefined> {
+  if (!DIAL_IN_CONFERENCE_MAPPER) {
+    return undefined;
+  }
+
+  try {
+    const {id} = await http(DIAL_IN_CONFERENCE_MAPPER, {searchParams: {conference: `${room}@${PROSODY_MUC_DOMAIN}`}})
+      .json<{id?: number}>();
+
+    return id;
+  }
+  catch (error) {
+    log.warn(`Resolving the PIN of ${room} failed: ${error.message}`, {room, errorKind: 'conference_mapper'});
+    return undefined;
+  }
+}
+
+/**
+ * Shows the phone numbers and the PIN of the room to logged in users.
+ */
+export async function dialIn(req: express.Request, res: express.Response) {
+  const {t, language} = translator(req);
+  const {room} = req.params;
+
+  const pin = await conferencePin(room);
+  const numbers = DIAL_IN_NUMBERS
+    .map(number => `<li><a href="${escapeHtml(`tel:${number.replace(/[^+\d]/g, '')}`)}">${escapeHtml(number)}</a></li>`)
+    .join('');
+
+  res.send(page(t('dialIn.title'), `
+    <p>${escapeHtml(t('dialIn.numbers', {room}))}</p>
+    <ul>${numbers}</ul>
+    <p>${escapeHtml(pin !== undefined ? t('dialIn.pin', {pin}) : t('dialIn.noPin'))}</p>
+  `, language));
+}
@@ -0,0 +1,43 @@
+import * as express from 'express';
+import {DirectoryRoom, ROOM_DIRECTORY, ROOM_DIRECTORY_CLAIM} from './config';
+import {escapeHtml, page} from './html';
+import {translator} from './i18n';
+
+/**
+ * Looks up a room of ROOM_DIRECTORY, room names like constructor don't match
+ * the properties of the object prototype.
+ */
+export function directoryRoom(room: string): DirectoryRoom | undefined {
+  return Object.prototype.hasOwnProperty.call(ROOM_DIRECTORY, room) ? ROOM_DIRECTORY[room] : undefined;
+}
+
+/**
+ * Reads a claim containing a list of groups or roles, nested claims are
+ * separated by dots (e.g. realm_access.roles of keycloak).
+ */
+export function claimValues(user: Record<string, unknown> | undefined, claim: string): string[] {
+  const value = claim.split('.').reduce<unknown>((object, key) => (object as Record<string, unknown> | undefined)?.[key], user);
+  return Array.isArray(value) ? value.map(String) : typeof value === 'string' ? [value] : [];
+}
+
+/**
+ * Lists the rooms of ROOM_DIRECTORY the logged in user may see: rooms
+ * without groups are listed for everyone, others if the user is member of one
+ * of their groups.
+ */
+export function directory(baseURL: string): express.RequestHandler {
+  return (req, res) => {
+    const {t, language} = translator(req);
+    const memberOf = claimValues(req.oidc.user, ROOM_DIRECTORY_CLAIM);
+
+    const items = Object.entries(ROOM_DIRECTORY)
+      .filter(([, room]) => !room.groups?.length || room.groups.some(group => memberOf.includes(group)))
+      .map(([name, room]) => `<li>
+        <span>${escapeHtml(room.label ?? name)}</span>
+        <a href="${escapeHtml(`${baseURL}/room/${encodeURIComponent(name)}`)}" role="button">${escapeHtml(t('join'))}</a>
+      </li>`)
+      .join('');
+
+    res.send(page(t('rooms.title'), items ? `<ul>${items}</ul>` : `<p>${escapeHtml(t('rooms.empty'))}</p>`, language));
+  };
+}
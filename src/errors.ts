@@ -0,0 +1,198 @@
+import * as express from 'express';
+import {randomBytes} from 'crypto';
+import {existsSync, readFileSync} from 'fs';
+import {join} from 'path';
+import {BASE_PATH, ERROR_TEMPLATES} from './config';
+import {escapeHtml, page} from './html';
+import {MessageKey, translator} from './i18n';
+import {getCookie, transientCookie} from './cookies';
+import {loginFailed} from './logins';
+import {annotate} from './log';
+
+const ROOM_COOKIE = 'last_room';
+// without characters that are easily confused (0/O, 1/I/L), the id is read out to support staff
+const ERROR_ID_ALPHABET = 'ABCDEFGHJKMNPQRSTUVWXYZ23456789';
+
+const TITLES: Record<number, MessageKey> = {
+  400: 'error.400',
+  401: 'error.401',
+  403: 'error.403',
+  404: 'error.404',
+  429: 'error.429',
+  500: 'error.title',
+  502: 'error.502',
+  503: 'error.503',
+};
+
+function template(name: string): string | undefined {
+  const path = ERROR_TEMPLATES && join(ERROR_TEMPLATES, name);
+  return path && existsSync(path) ? readFileSync(path, 'utf8') : undefined;
+}
+
+// custom templates are read once, {status}.html is preferred over error.html
+const TEMPLATES = new Map(Object.keys(TITLES).map(status => [Number(status), template(`${status}.html`)]));
+const DEFAULT_TEMPLATE = template('error.html');
+
+/**
+ * Classifies errors reaching the error handler, so cookie problems (lost
+ * state of the login), identity provider misconfiguration and outages can be
+ * told apart in the metrics.
+ */
+export function errorVariant(err: Error & {error?: string, type?: string, status?: number}): string {
+  switch (err.name) {
+    case 'OPError':
+      if (err.error === 'invalid_grant') {
+        return 'invalid_code';
+      }
+
+      return err.error === 'invalid_client' || err.error === 'unauthorized_client' ? 'idp_misconfigured' : 'idp_error';
+    case 'RPError':
+    case 'BadRequestError':
+      if (/state|nonce|checks\./i.test(err.message)) {
+        return 'invalid_state';
+      }
+
+      if (/expired|not active yet|\biat\b/i.test(err.message)) {
+        return 'clock_skew';
+      }
+
+      return /\b(iss|aud|alg|azp)\b|signature/i.test(err.message) ? 'idp_misconfigured' : 'invalid_response';
+    case 'RequestError':
+    case 'TimeoutError':
+    case 'HTTPError':
+    case 'ParseError':
+      return 'upstream_unreachable';
+  }
+
+  // errors of the body parser
+  if (err.type?.startsWith('entity.') || err.type === 'request.aborted') {
+    return 'invalid_request';
+  }
+
+  if (err.message === 'Missing user information.') {
+    return 'invalid_session';
+  }
+
+  return (err.status ?? 500) < 500 ? 'client_error' : 'internal';
+}
+
+/**
+ * Short random reference of a failed request (e.g. K7PQ-2XDM), which is added
+ * to the following log lines of the request.
+ */
+export function errorId(res: express.Response): string {
+  if (!res.locals.errorId) {
+    const id = [...randomBytes(8)].map(byte => ERROR_ID_ALPHABET[byte % ERROR_ID_ALPHABET.length]).join('');
+    res.locals.errorId = `${id.slice(0, 4)}-${id.slice(4)}`;
+    annotate({errorId: res.locals.errorId});
+  }
+
+  return res.locals.errorId;
+}
+
+/**
+ * Renders an error page with a link to retry (if given) or to go back to the
+ * landing page, the error id and the request id. Clients preferring JSON
+ * receive {error, error_id, request_id} instead.
+ */
+export function errorPage(req: express.Request, res: express.Response, status: number, message: MessageKey = 'error.message', retryUrl?: string) {
+  const {t, language} = translator(req);
+  const title = t(TITLES[status] ?? 'error.title');
+  const text = t(message);
+  const id = errorId(res);
+
+  res.status(status);
+
+  if (req.accepts(['html', 'json']) === 'json') {
+    res.json({error: text, error_id: id, request_id: req.id});
+    return;
+  }
+
+  const link = retryUrl ?? `${BASE_PATH}/`;
+  const custom = TEMPLATES.get(status) ?? DEFAULT_TEMPLATE;
+
+  if (custom) {
+    const values: Record<string, string> = {status: String(status), title, message: text, errorId: id, requestId: req.id, link, language};
+    res.send(custom.replace(/{{(\w+)}}/g, (placeholder, name: string) => name in values ? escapeHtml(values[name]) : placeholder));
+    return;
+  }
+
+  res.send(page(title, `
+    <p>${escapeHtml(text)}</p>
+    <p><a href="${escapeHtml(link)}">${escapeHtml(t(retryUrl ? 'retry' : 'error.home'))}</a></p>
+    <p><small>${escapeHtml(t('errorId'))}: <code>${escapeHtml(id)}</code>, ${escapeHtml(t('requestId'))}: <code>${escapeHtml(req.id)}</code></small></p>
+  `, language));
+}
+
+/**
+ * Passes exceptions and rejections of a (possibly async) handler to the error
+ * handler, which logs the stack trace with an error id and responds with 500.
+ * Otherwise a rejected handler leaves the request hanging until the timeout.
+ */
+export function catchErrors(handler: (req: express.Request, res: express.Response, next: express.NextFunction) => unknown): express.RequestHandler {
+  return (req, res, next) => {
+    Promise.resolve().then(() => handler(req, res, next)).catch(next);
+  };
+}
+
+/**
+ * Remembers the requested room, so the error page of a failed login is able
+ * to offer a retry link.
+ */
+export function rememberRoom(req: express.Request, res: express.Response, next: express.NextFunction) {
+  res.cookie(ROOM_COOKIE, req.params.room, transientCookie(req));
+  annotate({room: req.params.room});
+  next();
+}
+
+export function renderIdpError(req: express.Request, res: express.Response, error: string, description: string | undefined, retryUrl: string | undefined) {
+  const {t, language} = translator(req);
+  const retry = retryUrl ? `<p><a href="${escapeHtml(retryUrl)}">${escapeHtml(t('retry'))}</a></p>` : '';
+
+  res.status(error === 'access_denied' ? 403 : 400).send(page(t('idpError.title'), `
+    <p>${escapeHtml(t(description ? 'idpError.reason' : 'idpError.message'))}</p>
+    ${description ? `<blockquote>${escapeHtml(description)}</blockquote>` : ''}
+    <p><small>${escapeHtml(t('idpError.code'))}: <code>${escapeHtml(error)}</code>, ${escapeHtml(t('errorId'))}: <code>${escapeHtml(errorId(res))}</code>, ${escapeHtml(t('requestId'))}: <code>${escapeHtml(req.id)}</code></small></p>
+    ${retry}
+  `, language));
+}
+
+/**
+ * The login id of getLoginState, express-openid-connect passes the login state
+ * as base64url encoded JSON in the state parameter.
+ */
+function loginId(state: unknown): string | undefined {
+  if (typeof state !== 'string') {
+    return undefined;
+  }
+
+  try {
+    const {loginId} = JSON.parse(Buffer.from(state, 'base64url').toString());
+    return typeof loginId === 'string' ? loginId : undefined;
+  }
+  catch (error) {
+    return undefined;
+  }
+}
+
+/**
+ * Renders a friendly page if the identity provider redirected back with an
+ * error response (e.g. error=access_denied) instead of an authorization code.
+ */
+export function idpErrors(provider: string, baseURL: string): express.ErrorRequestHandler {
+  return (err, req, res, next) => {
+    const {error, error_description, state} = {...req.query, ...req.body};
+
+    if (typeof error !== 'string') {
+      next(err);
+      return;
+    }
+
+    loginFailed(req, provider, loginId(state), error);
+
+    const room = getCookie(req, ROOM_COOKIE);
+    const retryUrl = room ? `${baseURL}/room/${encodeURIComponent(room)}` : undefined;
+
+    renderIdpError(req, res, error, typeof error_description === 'string' ? error_description : undefined, retryUrl);
+  };
+}
@@ -0,0 +1,92 @@
+import * as express from 'express';
+import {BASE_PATH, CALLBACK_PATH, PROVIDERS, ROUTE_ALIASES} from './config';
+import {errorPage} from './errors';
+
+// paths of this service, which are never treated as room names
+const RESERVED = new Set([
+  'admin', 'api', 'callback', 'dashboard', 'device', 'display-name', 'favicon.ico', 'healthz', 'j', 'join', 'login',
+  'logout', 'matrix', 'metrics', 'readyz', 'robots.txt', 'room', 'rooms', 'slash-command', 'static', 'version',
+  CALLBACK_PATH.split('/')[1],
+  ...Object.keys(PROVIDERS),
+]);
+
+export function isReserved(segment: string): boolean {
+  return RESERVED.has(segment);
+}
+
+/**
+ * Treats unknown single segment paths as room names (/daily-standup →
+ * /room/daily-standup), so jitsi style links keep working if this service is
+ * placed on the meeting domain.
+ */
+export function roomFallback(req: express.Request, res: express.Response, next: express.NextFunction) {
+  const match = req.method === 'GET' ? req.path.match(/^\/([^/]+)\/?$/) : null;
+
+  if (!match || isReserved(match[1])) {
+    next();
+    return;
+  }
+
+  const query = req.originalUrl.indexOf('?');
+  res.redirect(`${BASE_PATH}/room/${match[1]}${query === -1 ? '' : req.originalUrl.slice(query)}`);
+}
+
+interface Alias {
+  pattern: RegExp;
+  names: string[];
+  room: string;
+}
+
+// /meet/{room} matches /meet/daily-standup, every placeholder matches a single segment
+const ALIASES: Alias[] = Object.entries(ROUTE_ALIASES).map(([path, room]) => {
+  const names: string[] = [];
+  const source = path.split(/({\w+})/).map(part => {
+    const placeholder = part.match(/^{(\w+)}$/);
+
+    if (!placeholder) {
+      return part.replace(/[.*+?^$()|[\]\\]/g, '\\$&');
+    }
+
+    names.push(placeholder[1]);
+    return '([^/]+)';
+  }).join('');
+
+  return {pattern: new RegExp(`^${source}/?$`), names, room};
+});
+
+/**
+ * Redirects the paths of ROUTE_ALIASES to the room flow, the room name is
+ * built from the placeholders (e.g. /{tenant}/{room} → {tenant}-{room}).
+ * Paths of this service are never treated as alias, malformed percent
+ * encoding is answered with 400.
+ */
+export function routeAliases(req: express.Request, res: express.Response, next: express.NextFunction) {
+  if (req.method !== 'GET' || isReserved(req.path.split('/')[1])) {
+    next();
+    return;
+  }
+
+  for (const {pattern, names, room} of ALIASES) {
+    const match = req.path.match(pattern);
+
+    if (match) {
+      let values: Record<string, string>;
+
+      try {
+        values = Object.fromEntries(names.map((name, index) => [name, decodeURIComponent(match[index + 1])]));
+      }
+      catch (error) {
+        errorPage(req, res, 400, 'error.invalidRoom');
+        return;
+      }
+
+      const query = req.originalUrl.indexOf('?');
+      const name = room.replace(/{(\w+)}/g, (placeholder, key: string) => values[key] ?? '');
+
+      res.redirect(`${BASE_PATH}/room/${encodeURIComponent(name)}${query === -1 ? '' : req.originalUrl.slice(query)}`);
+      return;
+    }
+  }
+
+  next();
+}
@@ -0,0 +1,265 @@
+import {readFileSync} from 'fs';
+import {isIPv4, isIPv6} from 'net';
+import {GEOIP_ASN_DATABASE, GEOIP_DATABASE} from './config';
+
+// the metadata follows the last occurrence of \xab\xcd\xefMaxMind.com
+const METADATA_MARKER = Buffer.from('abcdef4d61784d696e642e636f6d', 'hex');
+
+/**
+ * Decodes the data section format of MaxMind DB files
+ * (https://maxmind.github.io/MaxMind-DB/), pointers are relative to base.
+ */
+class Decoder {
+  constructor(private readonly buffer: Buffer, private readonly base: number) {}
+
+  decode(offset: number): [unknown, number] {
+    const control = this.buffer[offset++];
+    let type = control >> 5;
+
+    if (type === 1) {
+      const [pointer, next] = this.pointer(control, offset);
+      return [this.decode(this.base + pointer)[0], next];
+    }
+
+    if (type === 0) {
+      type = 7 + this.buffer[offset++];
+    }
+
+    let size = control & 0x1f;
+
+    if (size === 29) {
+      size = 29 + this.buffer[offset];
+      offset += 1;
+    }
+    else if (size === 30) {
+      size = 285 + this.buffer.readUInt16BE(offset);
+      offset += 2;
+    }
+    else if (size === 31) {
+      size = 65821 + this.buffer.readUIntBE(offset, 3);
+      offset += 3;
+    }
+
+    switch (type) {
+      case 2:
+        return [this.buffer.toString('utf8', offset, offset + size), offset + size];
+      case 3:
+        return [this.buffer.readDoubleBE(offset), offset + 8];
+      case 4:
+        return [this.buffer.slice(offset, offset + size), offset + size];
+      case 5:
+      case 6:
+      case 9:
+      case 10:
+        return [this.uint(offset, size), offset + size];
+      case 7: {
+        const map: Record<string, unknown> = {};
+
+        for (let i = 0; i < size; i++) {
+          const [key, value] = this.decode(offset);
+          const [entry, next] = this.decode(value);
+          map[key as string] = entry;
+          offset = next;
+        }
+
+        return [map, offset];
+      }
+      case 8:
+        return [this.uint(offset, size) | 0, offset + size];
+      case 11: {
+        const array: unknown[] = [];
+
+        for (let i = 0; i < size; i++) {
+          const [entry, next] = this.decode(offset);
+          array.push(entry);
+          offset = next;
+        }
+
+        return [array, offset];
+      }
+      case 14:
+        return [size !== 0, offset];
+      case 15:
+        return [this.buffer.readFloatBE(offset), offset + 4];
+      default:
+        throw new Error(`Unsupported MaxMind DB data type ${type}.`);
+    }
+  }
+
+  // 64 and 128 bit values lose precision, they aren't used by the country and ASN databases
+  private uint(offset: number, size: number): number {
+    let value = 0;
+
+    for (let i = 0; i < size; i++) {
+      value = value * 256 + this.buffer[offset + i];
+    }
+
+    return value;
+  }
+
+  private pointer(control: number, offset: number): [number, number] {
+    const value = control & 0x7;
+
+    switch ((control >> 3) & 0x3) {
+      case 0:
+        return [value * 0x100 + this.buffer[offset], offset + 1];
+      case 1:
+        return [value * 0x10000 + this.buffer.readUInt16BE(offset) + 2048, offset + 2];
+      case 2:
+        return [value * 0x1000000 + this.buffer.readUIntBE(offset, 3) + 526336, offset + 3];
+      default:
+        return [this.buffer.readUInt32BE(offset), offset + 4];
+    }
+  }
+}
+
+interface Metadata {
+  node_count: number;
+  record_size: number;
+  ip_version: number;
+}
+
+class MaxMindDatabase {
+  private readonly nodeCount: number;
+  private readonly recordSize: number;
+  private readonly ipVersion: number;
+  private readonly treeSize: number;
+  private readonly data: Decoder;
+  private readonly ipv4Start: number;
+
+  constructor(private readonly buffer: Buffer) {
+    const marker = buffer.lastIndexOf(METADATA_MARKER);
+
+    if (marker === -1) {
+      throw new Error('Not a MaxMind DB file.');
+    }
+
+    const start = marker + METADATA_MARKER.length;
+    const [metadata] = new Decoder(buffer, start).decode(start) as [Metadata, number];
+
+    this.nodeCount = metadata.node_count;
+    this.recordSize = metadata.record_size;
+    this.ipVersion = metadata.ip_version;
+    this.treeSize = this.recordSize * 2 / 8 * this.nodeCount;
+    // the search tree is followed by 16 zero bytes
+    this.data = new Decoder(buffer, this.treeSize + 16);
+
+    // ipv4 addresses are stored as ::a.b.c.d in ipv6 databases
+    let node = 0;
+
+    for (let i = 0; i < 96 && this.ipVersion === 6 && node < this.nodeCount; i++) {
+      node = this.record(node, 0);
+    }
+
+    this.ipv4Start = node;
+  }
+
+  private record(node: number, bit: number): number {
+    const offset = node * this.recordSize * 2 / 8;
+
+    switch (this.recordSize) {
+      case 24:
+        return this.buffer.readUIntBE(offset + bit * 3, 3);
+      case 28: {
+        const middle = this.buffer[offset + 3];
+        return bit
+          ? (middle & 0x0f) * 0x1000000 + this.buffer.readUIntBE(offset + 4, 3)
+          : (middle >> 4) * 0x1000000 + this.buffer.readUIntBE(offset, 3);
+      }
+      default:
+        return this.buffer.readUInt32BE(offset + bit * 4);
+    }
+  }
+
+  lookup(address: Buffer): unknown {
+    if (address.length === 16 && this.ipVersion === 4) {
+      return undefined;
+    }
+
+    let node = address.length === 4 ? this.ipv4Start : 0;
+
+    for (let i = 0; i < address.length * 8 && node < this.nodeCount; i++) {
+      node = this.record(node, (address[i >> 3] >> (7 - (i & 7))) & 1);
+    }
+
+    // the node count marks addresses without data
+    if (node <= this.nodeCount) {
+      return undefined;
+    }
+
+    return this.data.decode(this.treeSize + node - this.nodeCount)[0];
+  }
+}
+
+/**
+ * Converts an address to 4 or 16 bytes, ipv4 mapped ipv6 addresses (as
+ * reported by dual stack sockets) are treated as ipv4.
+ */
+function parseAddress(ip: string): Buffer | undefined {
+  const address = ip.replace(/^::ffff:(?=\d+\.\d+\.\d+\.\d+$)/i, '').replace(/%.*$/, '');
+
+  if (isIPv4(address)) {
+    return Buffer.from(address.split('.').map(Number));
+  }
+
+  if (!isIPv6(address)) {
+    return undefined;
+  }
+
+  // an embedded ipv4 address (e.g. 64:ff9b::192.0.2.1) is converted to two groups
+  const text = address.replace(/(\d+)\.(\d+)\.(\d+)\.(\d+)$/, (match, a, b, c, d) =>
+    `${(Number(a) * 256 + Number(b)).toString(16)}:${(Number(c) * 256 + Number(d)).toString(16)}`);
+
+  const [head, tail] = text.split('::');
+  const headGroups = head ? head.split(':') : [];
+  const tailGroups = tail ? tail.split(':') : [];
+  const groups = tail === undefined
+    ? headGroups
+    : [...headGroups, ...Array(8 - headGroups.length - tailGroups.length).fill('0'), ...tailGroups];
+
+  const buffer = Buffer.alloc(16);
+  groups.forEach((group, index) => buffer.writeUInt16BE(parseInt(group, 16), index * 2));
+
+  return buffer;
+}
+
+const countries = GEOIP_DATABASE ? new MaxMindDatabase(readFileSync(GEOIP_DATABASE)) : undefined;
+const asns = GEOIP_ASN_DATABASE ? new MaxMindDatabase(readFileSync(GEOIP_ASN_DATABASE)) : undefined;
+
+interface CountryRecord {
+  country?: {iso_code?: string};
+  registered_country?: {iso_code?: string};
+}
+
+interface AsnRecord {
+  autonomous_system_number?: number;
+  autonomous_system_organization?: string;
+}
+
+export interface Location {
+  country?: string;
+  asn?: number;
+  asOrganization?: string;
+}
+
+/**
+ * Looks up the country (GEOIP_DATABASE, e.g. GeoLite2-Country or City) and
+ * the autonomous system (GEOIP_ASN_DATABASE) of a client, fields without a
+ * database or a match are omitted.
+ */
+export function locate(ip: string | undefined): Location {
+  const address = ip && (countries || asns) ? parseAddress(ip) : undefined;
+
+  if (!address) {
+    return {};
+  }
+
+  const country = countries?.lookup(address) as CountryRecord | undefined;
+  const asn = asns?.lookup(address) as AsnRecord | undefined;
+
+  return {
+    country: country?.country?.iso_code ?? country?.registered_country?.iso_code,
+    asn: asn?.autonomous_system_number,
+    asOrganization: asn?.autonomous_system_organization,
+  };
+}
@@ -0,0 +1,104 @@
+import * as express from 'express';
+import {createHash, randomBytes, timingSafeEqual} from 'crypto';
+import {BODY_LIMIT, DISPLAY_NAME_CONFIRM, DISPLAY_NAME_MAX_LENGTH, ROOM_PASSCODES} from './config';
+import {escapeHtml, page} from './html';
+import {translator, Translator} from './i18n';
+import {joinRoom, queryString, validateDisplayName} from './join';
+import * as log from './log';
+import {audit} from './audit';
+import {captchaWidget, isCaptchaSolved} from './captcha';
+import {catchErrors} from './errors';
+
+function digest(value: string): Buffer {
+  return createHash('sha256').update(value).digest();
+}
+
+// room names like constructor must not match the properties of the object prototype
+function roomPasscode(room: string): string | undefined {
+  return Object.prototype.hasOwnProperty.call(ROOM_PASSCODES, room) ? ROOM_PASSCODES[room] : undefined;
+}
+
+function isPasscode(room: string, passcode: string): boolean {
+  const expected = roomPasscode(room);
+  return !!expected && timingSafeEqual(digest(passcode), digest(expected));
+}
+
+export function hasPasscode(room: string): boolean {
+  return roomPasscode(room) !== undefined;
+}
+
+/**
+ * Without DISPLAY_NAME_CONFIRM guests enter their name here, otherwise it is
+ * asked for on the following page.
+ */
+function guestPage({t, language}: Translator, action: string, room: string, name = '', problem?: string): string {
+  return page(t('guest.title'), `
+    <p>${escapeHtml(t('guest.intro', {room}))}</p>
+    ${problem ? `<p role="alert">${escapeHtml(problem)}</p>` : ''}
+    <form action="${escapeHtml(action)}" method="post">
+      ${DISPLAY_NAME_CONFIRM ? '' : `
+        <label for="name">${escapeHtml(t('displayName.label'))}</label>
+        <input id="name" name="name" value="${escapeHtml(name)}" maxlength="${DISPLAY_NAME_MAX_LENGTH}" required autofocus>
+      `}
+      <label for="passcode">${escapeHtml(t('guest.passcode'))}</label>
+      <input id="passcode" name="passcode" type="password" autocomplete="off" required>
+      ${captchaWidget()}
+      <button type="submit">${escapeHtml(t('join'))}</button>
+    </form>
+  `, language);
+}
+
+export const guests = express.Router();
+
+/**
+ * Visitors without an account at the identity provider join rooms of
+ * ROOM_PASSCODES using the passcode, they receive a token for this room only.
+ */
+guests.get('/room/:room/guest', (req, res, next) => {
+  if (!hasPasscode(req.params.room)) {
+    next();
+    return;
+  }
+
+  res.send(guestPage(translator(req), req.originalUrl, req.params.room));
+});
+
+guests.post('/room/:room/guest', express.urlencoded({extended: false, limit: BODY_LIMIT}), catchErrors(async (req, res, next) => {
+  const {room} = req.params;
+
+  if (!hasPasscode(room)) {
+    next();
+    return;
+  }
+
+  const messages = translator(req);
+  const name = typeof req.body?.name === 'string' ? req.body.name.trim().replace(/\s+/g, ' ') : messages.t('guest.name');
+  const passcode = typeof req.body?.passcode === 'string' ? req.body.passcode : '';
+
+  // before the passcode, bots must not be able to guess it
+  if (!await isCaptchaSolved(req)) {
+    log.warn(`[audit] rejected guest of ${room} from ${req.ip}: captcha not solved`, {room, errorKind: 'captcha'});
+    audit(req, 'access_denied', {flow: 'guest', room, reason: 'captcha'});
+    res.status(403).send(guestPage(messages, req.originalUrl, room, name, messages.t('guest.captcha')));
+    return;
+  }
+
+  if (!isPasscode(room, passcode)) {
+    log.warn(`[audit] rejected guest of ${room} from ${req.ip}: wrong passcode`, {room, errorKind: 'invalid_passcode'});
+    audit(req, 'access_denied', {flow: 'guest', room, reason: 'invalid_passcode'});
+    res.status(403).send(guestPage(messages, req.originalUrl, room, name, messages.t('guest.invalid')));
+    return;
+  }
+
+  const problem = validateDisplayName(messages, name);
+
+  if (problem) {
+    res.status(400).send(guestPage(messages, req.originalUrl, room, name, problem));
+    return;
+  }
+
+  const id = `guest-${randomBytes(8).toString('hex')}`;
+  log.info(`[audit] guest ${id} joined ${room} using the passcode from ${req.ip}`, {room});
+
+  await joinRoom(req, res, {id, name, email: '', moderator: false}, room, 'guest', queryString(req));
+}));
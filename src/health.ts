@@ -0,0 +1,112 @@
+import * as express from 'express';
+import {HEALTH_CHECK_INTERVAL, HEALTH_CHECK_JITSI, JITSI_BACKENDS, JITSI_URL} from './config';
+import {http} from './http-client';
+import * as log from './log';
+import {getClient, isDiscovered, oidcProviders} from './oidc';
+import {isDraining} from './shutdown';
+
+interface Check {
+  ok: boolean;
+  checked: string;
+  durationMs: number;
+  error?: string;
+}
+
+const checks = new Map<string, Check>();
+
+async function check(name: string, probe: () => Promise<unknown>) {
+  const start = Date.now();
+
+  try {
+    await probe();
+    checks.set(name, {ok: true, checked: new Date().toISOString(), durationMs: Date.now() - start});
+  }
+  catch (error) {
+    if (checks.get(name)?.ok !== false) {
+      log.warn(`Health check of ${name} failed: ${error.message}`, {errorKind: 'health_check'});
+    }
+
+    checks.set(name, {ok: false, checked: new Date().toISOString(), durationMs: Date.now() - start, error: error.message});
+  }
+}
+
+/**
+ * The jitsi deployments probed with HEALTH_CHECK_JITSI, by check name (jitsi
+ * for JITSI_URL, jitsi:<name> for the backends).
+ */
+function jitsiChecks(): Record<string, string> {
+  if (!HEALTH_CHECK_JITSI) {
+    return {};
+  }
+
+  return {
+    jitsi: JITSI_URL,
+    ...Object.fromEntries(Object.entries(JITSI_BACKENDS).map(([name, {url}]) => [`jitsi:${name}`, url])),
+  };
+}
+
+function checkNames(): string[] {
+  return [...oidcProviders(), ...Object.keys(jitsiChecks())];
+}
+
+/**
+ * Fetches the keys of every OpenID Connect provider (which requires the
+ * metadata), without retries, the next probe follows soon enough. Jitsi only
+ * has to answer a HEAD request without a server error.
+ */
+async function runChecks() {
+  await Promise.all([
+    ...oidcProviders().map(name => check(name, async () => {
+      const {issuer} = await getClient(name);
+      await http(issuer.metadata.jwks_uri as string, {retry: 0}).json();
+    })),
+    ...Object.entries(jitsiChecks()).map(([name, url]) => check(name, async () => {
+      const {statusCode} = await http.head(url, {retry: 0, throwHttpErrors: false, followRedirect: false});
+
+      if (statusCode >= 500) {
+        throw new Error(`Response code ${statusCode} (${url})`);
+      }
+    })),
+  ]);
+}
+
+/**
+ * Probes the identity providers (and jitsi, with HEALTH_CHECK_JITSI) every
+ * HEALTH_CHECK_INTERVAL seconds, the results are reported by /readyz?deep=1.
+ */
+export function startHealthChecks() {
+  if (!HEALTH_CHECK_INTERVAL) {
+    return;
+  }
+
+  runChecks();
+  setInterval(runChecks, HEALTH_CHECK_INTERVAL * 1000).unref();
+}
+
+export const health = express.Router();
+
+health.get('/healthz', (req, res) => {
+  res.json({status: 'ok'});
+});
+
+/**
+ * Ready as soon as the discovery of every OpenID Connect provider completed,
+ * until the shutdown started. With ?deep=1 the last probes of the identity
+ * providers and jitsi have to be successful as well (if HEALTH_CHECK_INTERVAL
+ * is set).
+ */
+health.get('/readyz', (req, res) => {
+  const providers = Object.fromEntries(oidcProviders().map(name => [name, isDiscovered(name)]));
+  let ready = !isDraining() && Object.values(providers).every(Boolean);
+
+  if (req.query.deep !== '1' || !HEALTH_CHECK_INTERVAL) {
+    res.status(ready ? 200 : 503).json({status: ready ? 'ok' : 'unavailable', providers});
+    return;
+  }
+
+  // checks which didn't run yet count as failing
+  const deep = Object.fromEntries(checkNames().map(name => [name, checks.get(name) ?? {ok: false}]));
+  ready = ready && Object.values(deep).every(({ok}) => ok);
+
+  res.status(ready ? 200 : 503).json({status: ready ? 'ok' : 'unavailable', providers, checks: deep});
+});
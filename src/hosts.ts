@@ -0,0 +1,45 @@
+import {ROOM_DIRECTORY, ROOM_DIRECTORY_CLAIM} from './config';
+import {claimValues, directoryRoom} from './directory';
+import {JitsiUser} from './jitsi';
+
+// jitsi doesn't tell when the host left, a host token counts as present for the length of a long meeting
+const HOST_PRESENCE = 4 * 60 * 60 * 1000;
+const HOSTED_ROOMS = Object.values(ROOM_DIRECTORY).some(room => room.hosts?.length);
+
+const hostsJoined = new Map<string, number>();
+
+/**
+ * Keeps the groups of the user with the identity, only if a room of
+ * ROOM_DIRECTORY requires a host.
+ */
+export function hostGroups(claims: Record<string, unknown> | undefined): Pick<JitsiUser, 'groups'> {
+  return HOSTED_ROOMS ? {groups: claimValues(claims, ROOM_DIRECTORY_CLAIM)} : {};
+}
+
+export function requiresHost(room: string): boolean {
+  return !!directoryRoom(room)?.hosts?.length;
+}
+
+export function isHost(user: JitsiUser, room: string): boolean {
+  const hosts = directoryRoom(room)?.hosts ?? [];
+  return !!user.groups?.some(group => hosts.includes(group));
+}
+
+export function hostJoined(room: string) {
+  hostsJoined.set(room, Date.now());
+}
+
+export function hasHost(room: string): boolean {
+  const joined = hostsJoined.get(room);
+
+  if (joined === undefined) {
+    return false;
+  }
+
+  if (Date.now() - joined > HOST_PRESENCE) {
+    hostsJoined.delete(room);
+    return false;
+  }
+
+  return true;
+}
@@ -0,0 +1,38 @@
+import {LOGO_URL, STYLESHEET_URL} from './static';
+
+const ESCAPES: Record<string, string> = {
+  '&': '&amp;',
+  '<': '&lt;',
+  '>': '&gt;',
+  '"': '&quot;',
+  '\'': '&#39;',
+};
+
+export function escapeHtml(value: string): string {
+  return value.replace(/[&<>"']/g, char => ESCAPES[char]);
+}
+
+// JSON inside a script element must not be able to close it
+export function scriptJson(value: unknown): string {
+  return JSON.stringify(value).replace(/</g, '\\u003c');
+}
+
+export function page(title: string, body: string, language = 'en'): string {
+  return `<!DOCTYPE html>
+<html lang="${escapeHtml(language)}">
+<head>
+  <meta charset="utf-8">
+  <meta name="viewport" content="width=device-width, initial-scale=1">
+  <title>${escapeHtml(title)}</title>
+  ${STYLESHEET_URL ? `<link rel="stylesheet" href="${escapeHtml(STYLESHEET_URL)}">` : ''}
+</head>
+<body>
+  ${LOGO_URL ? `<header><img src="${escapeHtml(LOGO_URL)}" alt="Logo"></header>` : ''}
+  <main>
+    <h1>${escapeHtml(title)}</h1>
+    ${body}
+  </main>
+</body>
+</html>
+`;
+}
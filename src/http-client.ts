@@ -0,0 +1,112 @@
+import got, {Hooks, NormalizedOptions, RequiredRetryOptions} from 'got';
+import {custom} from 'openid-client';
+import {performance} from 'perf_hooks';
+import {HTTP_CLIENT_CONNECT_TIMEOUT, HTTP_CLIENT_RETRIES, HTTP_CLIENT_TIMEOUT, PROVIDERS} from './config';
+import {idpRequestDuration} from './metrics';
+import {recordTiming} from './trace';
+import {endRequestSpan, startRequestSpan} from './tracing';
+
+const RETRY_BASE_DELAY = 200;
+const RETRY_MAX_DELAY = 2000;
+
+/**
+ * Exponential backoff with jitter, so the retries of many logins don't hit a
+ * recovering identity provider at the same time.
+ */
+function retryDelay({attemptCount, computedValue}: {attemptCount: number, computedValue: number}): number {
+  if (!computedValue) {
+    return 0;
+  }
+
+  return Math.min(RETRY_BASE_DELAY * 2 ** (attemptCount - 1), RETRY_MAX_DELAY) * (0.5 + Math.random() / 2);
+}
+
+/**
+ * Requests to identity providers are retried on connection errors and
+ * gateway errors only. Authorization codes are single use, a retry of a token
+ * request that reached the provider would fail anyway.
+ */
+const retry: Partial<RequiredRetryOptions> = {
+  limit: HTTP_CLIENT_RETRIES,
+  methods: ['GET', 'POST'],
+  statusCodes: [502, 503, 504],
+  errorCodes: ['ECONNREFUSED', 'ECONNRESET', 'EAI_AGAIN', 'ENOTFOUND', 'ETIMEDOUT'],
+  calculateDelay: retryDelay,
+};
+
+const timeout = {
+  connect: HTTP_CLIENT_CONNECT_TIMEOUT * 1000,
+  request: HTTP_CLIENT_TIMEOUT * 1000,
+};
+
+const endpoints = new Map<string, string>();
+const started = new WeakMap<NormalizedOptions, number>();
+
+function endpoint(url: URL): string {
+  return `${url.origin}${url.pathname}`;
+}
+
+/**
+ * Registers endpoints of an identity provider by operation (token, jwks, ...),
+ * requests to them are recorded in the latency histogram.
+ */
+export function idpEndpoints(operations: Record<string, unknown>) {
+  for (const [operation, url] of Object.entries(operations)) {
+    if (typeof url === 'string') {
+      endpoints.set(endpoint(new URL(url)), operation);
+    }
+  }
+}
+
+for (const provider of Object.values(PROVIDERS)) {
+  if (provider.type === 'oauth2') {
+    idpEndpoints({token: provider.tokenURL, userinfo: provider.userinfoURL});
+  }
+}
+
+function observe(options: NormalizedOptions, outcome: string) {
+  const operation = options.url.pathname.endsWith('/.well-known/openid-configuration') ? 'discovery' : endpoints.get(endpoint(options.url));
+  const start = started.get(options);
+
+  if (start === undefined) {
+    return;
+  }
+
+  const duration = performance.now() - start;
+  recordTiming(operation ?? 'upstream', duration);
+
+  if (operation) {
+    idpRequestDuration.observe({operation, outcome}, duration / 1000);
+  }
+}
+
+/**
+ * Times every attempt, so slow logins can be attributed to the identity
+ * provider. Other requests (prosody, matrix, ...) aren't recorded in the
+ * histogram, but traced and added to the timings of slow requests as well.
+ */
+const hooks: Partial<Hooks> = {
+  beforeRequest: [options => {
+    started.set(options, performance.now());
+    startRequestSpan(options);
+  }],
+  afterResponse: [response => {
+    observe(response.request.options, response.statusCode < 400 ? 'success' : 'http_error');
+    endRequestSpan(response.request.options, response.statusCode);
+    return response;
+  }],
+  beforeError: [error => {
+    // http errors are already recorded as response
+    if (!error.response) {
+      observe(error.options, error.name === 'TimeoutError' ? 'timeout' : 'network_error');
+      endRequestSpan(error.options);
+    }
+
+    return error;
+  }],
+};
+
+export const http = got.extend({timeout, retry, hooks});
+
+// also applies to the discovery and token requests of express-openid-connect
+custom.setHttpOptionsDefaults({timeout, retry, hooks});
@@ -0,0 +1,391 @@
+import * as express from 'express';
+import {DEFAULT_LANGUAGE, TRANSLATIONS} from './config';
+import {getPreferences} from './preferences';
+
+const en = {
+  'join': 'Join',
+  'landing.intro': 'Sign in with your organization account to join a meeting.',
+  'landing.room': 'Room name',
+  'chooser.title': 'Sign in',
+  'chooser.intro': 'Choose how you want to sign in:',
+  'guest.title': 'Join as guest',
+  'guest.intro': 'Enter the passcode of {room} you received with the invitation.',
+  'guest.name': 'Guest',
+  'guest.passcode': 'Passcode',
+  'guest.invalid': 'The passcode is wrong.',
+  'guest.captcha': 'Please confirm that you are not a robot.',
+  'chooser.guest': 'Join as guest with a passcode',
+  'rooms.title': 'Rooms',
+  'rooms.empty': 'There are no rooms available to you.',
+  'dialIn.title': 'Join by phone',
+  'dialIn.numbers': 'Call one of these numbers to join {room}:',
+  'dialIn.pin': 'Enter the PIN {pin} followed by #.',
+  'dialIn.noPin': 'The PIN is announced when you call.',
+  'inviteMail.title': 'Invite people',
+  'inviteMail.intro': 'Send an invitation to {room} by email.',
+  'inviteMail.recipients': 'Email addresses (separated by commas or line breaks)',
+  'inviteMail.message': 'Personal message (optional)',
+  'inviteMail.send': 'Send invitations',
+  'inviteMail.empty': 'Enter at least one email address.',
+  'inviteMail.invalid': '{address} is not a valid email address.',
+  'inviteMail.tooMany': 'At most {max} people can be invited at once.',
+  'inviteMail.failed': 'The invitations could not be sent, please try again later.',
+  'inviteMail.sent': 'The invitation to {room} was sent to {count} recipients.',
+  'inviteMail.subject': '{sender} invites you to {room}',
+  'inviteMail.body': '{sender} invites you to the meeting {room}. Join using this link:',
+  'inviteMail.dialIn': 'Join by phone:',
+  'app.title': 'Join meeting',
+  'app.open': 'Open in the Jitsi Meet app',
+  'app.browser': 'Continue in the browser',
+  'embed.title': 'Signed in',
+  'embed.message': 'You are signed in, this window can be closed.',
+  'embed.forbidden.title': 'Embedding not allowed',
+  'embed.forbidden.message': 'The origin {origin} is not allowed to embed this service.',
+  'displayName.title': 'Your name',
+  'displayName.intro': 'This name is shown to the other participants of {room}.',
+  'displayName.label': 'Display name',
+  'displayName.length': 'The name has to be between {min} and {max} characters long.',
+  'displayName.characters': 'The name contains characters which are not allowed.',
+  'welcome.schedule': 'When',
+  'welcome.organizer': 'Organizer',
+  'welcome.join': 'Join now',
+  'waiting.title': 'Waiting for the host',
+  'waiting.message': 'The meeting {room} starts as soon as the host has joined. This page continues automatically.',
+  'waiting.retry': 'Try again',
+  'expired.title': 'Sign in expired',
+  'expired.retry': 'Sign in again',
+  'qr.caption': 'Scan to join or open',
+  'link.title': 'Link expired',
+  'link.message': 'This link is invalid or expired, ask for a new invitation.',
+  'link.landing': 'Enter the room name',
+  'idpError.title': 'Sign in failed',
+  'idpError.message': 'The identity provider did not sign you in.',
+  'idpError.reason': 'The identity provider did not sign you in:',
+  'idpError.code': 'Error code',
+  'retry': 'Try again',
+  'error.title': 'Something went wrong',
+  'error.message': 'Your request could not be completed.',
+  'error.400': 'Invalid request',
+  'error.401': 'Not signed in',
+  'error.403': 'Access denied',
+  'error.404': 'Page not found',
+  'error.429': 'Too many requests',
+  'error.502': 'Service not reachable',
+  'error.503': 'Temporarily unavailable',
+  'error.home': 'Back to the start page',
+  'error.notFound': 'The page you requested does not exist.',
+  'error.rateLimited': 'You sent too many requests, please wait a moment and try again.',
+  'error.shuttingDown': 'The service is restarting, please try again in a few seconds.',
+  'error.jitsiUnreachable': 'The meeting service is not reachable right now, please try again later.',
+  'error.invalidState': 'The sign in could not be completed, please start again.',
+  'error.missingToken': 'An access token is required to join this meeting.',
+  'error.invalidToken': 'The access token is invalid or expired.',
+  'error.invalidInvite': 'The start or duration of the invitation is invalid.',
+  'error.invalidRoom': 'This room name is not allowed, please choose another one.',
+  'error.expiredForm': 'The form expired, please reload the page and try again.',
+  'error.linkTooLong': 'The link of this room is too long for a QR code.',
+  'errorId': 'Error ID',
+  'requestId': 'Request ID',
+  'maintenance.message': 'Meetings are currently unavailable due to planned maintenance. Please try again later.',
+};
+
+export type MessageKey = keyof typeof en;
+
+type Messages = Partial<Record<MessageKey, string>>;
+
+const de: Messages = {
+  'join': 'Beitreten',
+  'landing.intro': 'Melde dich mit dem Konto deiner Organisation an, um einer Besprechung beizutreten.',
+  'landing.room': 'Raumname',
+  'chooser.title': 'Anmelden',
+  'chooser.intro': 'Wähle aus, wie du dich anmelden möchtest:',
+  'guest.title': 'Als Gast beitreten',
+  'guest.intro': 'Gib den Code für {room} ein, den du mit der Einladung erhalten hast.',
+  'guest.name': 'Gast',
+  'guest.passcode': 'Code',
+  'guest.invalid': 'Der Code ist falsch.',
+  'guest.captcha': 'Bitte bestätige, dass du kein Roboter bist.',
+  'chooser.guest': 'Als Gast mit Code beitreten',
+  'rooms.title': 'Räume',
+  'rooms.empty': 'Für dich sind keine Räume verfügbar.',
+  'dialIn.title': 'Per Telefon teilnehmen',
+  'dialIn.numbers': 'Rufe eine dieser Nummern an, um {room} beizutreten:',
+  'dialIn.pin': 'Gib die PIN {pin} gefolgt von # ein.',
+  'dialIn.noPin': 'Die PIN wird beim Anruf abgefragt.',
+  'inviteMail.title': 'Personen einladen',
+  'inviteMail.intro': 'Eine Einladung zu {room} per E-Mail versenden.',
+  'inviteMail.recipients': 'E-Mail-Adressen (durch Kommas oder Zeilenumbrüche getrennt)',
+  'inviteMail.message': 'Persönliche Nachricht (optional)',
+  'inviteMail.send': 'Einladungen versenden',
+  'inviteMail.empty': 'Gib mindestens eine E-Mail-Adresse ein.',
+  'inviteMail.invalid': '{address} ist keine gültige E-Mail-Adresse.',
+  'inviteMail.tooMany': 'Es können höchstens {max} Personen auf einmal eingeladen werden.',
+  'inviteMail.failed': 'Die Einladungen konnten nicht versendet werden, bitte versuche es später erneut.',
+  'inviteMail.sent': 'Die Einladung zu {room} wurde an {count} Personen versendet.',
+  'inviteMail.subject': '{sender} lädt dich zu {room} ein',
+  'inviteMail.body': '{sender} lädt dich zur Besprechung {room} ein. Tritt über diesen Link bei:',
+  'inviteMail.dialIn': 'Per Telefon beitreten:',
+  'app.title': 'Besprechung beitreten',
+  'app.open': 'In der Jitsi Meet App öffnen',
+  'app.browser': 'Im Browser fortfahren',
+  'embed.title': 'Angemeldet',
+  'embed.message': 'Du bist angemeldet, dieses Fenster kann geschlossen werden.',
+  'embed.forbidden.title': 'Einbetten nicht erlaubt',
+  'embed.forbidden.message': 'Der Ursprung {origin} darf diesen Dienst nicht einbetten.',
+  'displayName.title': 'Dein Name',
+  'displayName.intro': 'Dieser Name wird den anderen Teilnehmenden von {room} angezeigt.',
+  'displayName.label': 'Anzeigename',
+  'displayName.length': 'Der Name muss zwischen {min} und {max} Zeichen lang sein.',
+  'displayName.characters': 'Der Name enthält nicht erlaubte Zeichen.',
+  'welcome.schedule': 'Wann',
+  'welcome.organizer': 'Organisation',
+  'welcome.join': 'Jetzt beitreten',
+  'waiting.title': 'Warten auf die Moderation',
+  'waiting.message': 'Die Besprechung {room} beginnt, sobald die Moderation beigetreten ist. Diese Seite geht automatisch weiter.',
+  'waiting.retry': 'Erneut versuchen',
+  'expired.title': 'Anmeldung abgelaufen',
+  'expired.retry': 'Erneut anmelden',
+  'qr.caption': 'Zum Beitreten scannen oder öffnen',
+  'link.title': 'Link abgelaufen',
+  'link.message': 'Dieser Link ist ungültig oder abgelaufen, bitte um eine neue Einladung.',
+  'link.landing': 'Raumnamen eingeben',
+  'idpError.title': 'Anmeldung fehlgeschlagen',
+  'idpError.message': 'Der Identitätsanbieter hat dich nicht angemeldet.',
+  'idpError.reason': 'Der Identitätsanbieter hat dich nicht angemeldet:',
+  'idpError.code': 'Fehlercode',
+  'retry': 'Erneut versuchen',
+  'error.title': 'Etwas ist schiefgelaufen',
+  'error.message': 'Deine Anfrage konnte nicht abgeschlossen werden.',
+  'error.400': 'Ungültige Anfrage',
+  'error.401': 'Nicht angemeldet',
+  'error.403': 'Zugriff verweigert',
+  'error.404': 'Seite nicht gefunden',
+  'error.429': 'Zu viele Anfragen',
+  'error.502': 'Dienst nicht erreichbar',
+  'error.503': 'Vorübergehend nicht verfügbar',
+  'error.home': 'Zurück zur Startseite',
+  'error.notFound': 'Die angeforderte Seite existiert nicht.',
+  'error.rateLimited': 'Du hast zu viele Anfragen gesendet, bitte warte einen Moment und versuche es erneut.',
+  'error.shuttingDown': 'Der Dienst wird neu gestartet, bitte versuche es in ein paar Sekunden erneut.',
+  'error.jitsiUnreachable': 'Der Besprechungsdienst ist gerade nicht erreichbar, bitte versuche es später erneut.',
+  'error.invalidState': 'Die Anmeldung konnte nicht abgeschlossen werden, bitte beginne von vorne.',
+  'error.missingToken': 'Für diese Besprechung ist ein Zugriffstoken erforderlich.',
+  'error.invalidToken': 'Das Zugriffstoken ist ungültig oder abgelaufen.',
+  'error.invalidInvite': 'Beginn oder Dauer der Einladung sind ungültig.',
+  'error.invalidRoom': 'Dieser Raumname ist nicht erlaubt, bitte wähle einen anderen.',
+  'error.expiredForm': 'Das Formular ist abgelaufen, bitte lade die Seite neu und versuche es erneut.',
+  'error.linkTooLong': 'Der Link dieses Raums ist zu lang für einen QR-Code.',
+  'errorId': 'Fehler-ID',
+  'requestId': 'Anfrage-ID',
+  'maintenance.message': 'Besprechungen sind wegen geplanter Wartungsarbeiten derzeit nicht verfügbar. Bitte versuche es später erneut.',
+};
+
+const fr: Messages = {
+  'join': 'Rejoindre',
+  'landing.intro': 'Connectez-vous avec le compte de votre organisation pour rejoindre une réunion.',
+  'landing.room': 'Nom de la salle',
+  'chooser.title': 'Connexion',
+  'chooser.intro': 'Choisissez comment vous connecter :',
+  'guest.title': 'Rejoindre en tant qu\'invité',
+  'guest.intro': 'Saisissez le code de {room} que vous avez reçu avec l\'invitation.',
+  'guest.name': 'Invité',
+  'guest.passcode': 'Code',
+  'guest.invalid': 'Le code est incorrect.',
+  'guest.captcha': 'Veuillez confirmer que vous n\'êtes pas un robot.',
+  'chooser.guest': 'Rejoindre en tant qu\'invité avec un code',
+  'rooms.title': 'Salles',
+  'rooms.empty': 'Aucune salle ne vous est accessible.',
+  'dialIn.title': 'Participer par téléphone',
+  'dialIn.numbers': 'Appelez l\'un de ces numéros pour rejoindre {room} :',
+  'dialIn.pin': 'Saisissez le code PIN {pin} suivi de #.',
+  'dialIn.noPin': 'Le code PIN vous est demandé lors de l\'appel.',
+  'inviteMail.title': 'Inviter des personnes',
+  'inviteMail.intro': 'Envoyer une invitation à {room} par e-mail.',
+  'inviteMail.recipients': 'Adresses e-mail (séparées par des virgules ou des retours à la ligne)',
+  'inviteMail.message': 'Message personnel (facultatif)',
+  'inviteMail.send': 'Envoyer les invitations',
+  'inviteMail.empty': 'Saisissez au moins une adresse e-mail.',
+  'inviteMail.invalid': '{address} n\'est pas une adresse e-mail valide.',
+  'inviteMail.tooMany': 'Vous pouvez inviter au plus {max} personnes à la fois.',
+  'inviteMail.failed': 'Les invitations n\'ont pas pu être envoyées, veuillez réessayer plus tard.',
+  'inviteMail.sent': 'L\'invitation à {room} a été envoyée à {count} destinataires.',
+  'inviteMail.subject': '{sender} vous invite à {room}',
+  'inviteMail.body': '{sender} vous invite à la réunion {room}. Rejoignez-la avec ce lien :',
+  'inviteMail.dialIn': 'Rejoindre par téléphone :',
+  'app.title': 'Rejoindre la réunion',
+  'app.open': 'Ouvrir dans l\'application Jitsi Meet',
+  'app.browser': 'Continuer dans le navigateur',
+  'embed.title': 'Connecté',
+  'embed.message': 'Vous êtes connecté, cette fenêtre peut être fermée.',
+  'embed.forbidden.title': 'Intégration non autorisée',
+  'embed.forbidden.message': 'L\'origine {origin} n\'est pas autorisée à intégrer ce service.',
+  'displayName.title': 'Votre nom',
+  'displayName.intro': 'Ce nom est affiché aux autres participants de {room}.',
+  'displayName.label': 'Nom affiché',
+  'displayName.length': 'Le nom doit contenir entre {min} et {max} caractères.',
+  'displayName.characters': 'Le nom contient des caractères non autorisés.',
+  'welcome.schedule': 'Quand',
+  'welcome.organizer': 'Organisateur',
+  'welcome.join': 'Rejoindre maintenant',
+  'waiting.title': 'En attente de l\'hôte',
+  'waiting.message': 'La réunion {room} commence dès que l\'hôte l\'a rejointe. Cette page continue automatiquement.',
+  'waiting.retry': 'Réessayer',
+  'expired.title': 'Connexion expirée',
+  'expired.retry': 'Se reconnecter',
+  'qr.caption': 'Scannez pour rejoindre ou ouvrez',
+  'link.title': 'Lien expiré',
+  'link.message': 'Ce lien est invalide ou a expiré, demandez une nouvelle invitation.',
+  'link.landing': 'Saisir le nom de la salle',
+  'idpError.title': 'Échec de la connexion',
+  'idpError.message': 'Le fournisseur d\'identité ne vous a pas connecté.',
+  'idpError.reason': 'Le fournisseur d\'identité ne vous a pas connecté :',
+  'idpError.code': 'Code d\'erreur',
+  'retry': 'Réessayer',
+  'error.title': 'Une erreur est survenue',
+  'error.message': 'Votre demande n\'a pas pu aboutir.',
+  'error.400': 'Requête invalide',
+  'error.401': 'Non connecté',
+  'error.403': 'Accès refusé',
+  'error.404': 'Page introuvable',
+  'error.429': 'Trop de requêtes',
+  'error.502': 'Service injoignable',
+  'error.503': 'Temporairement indisponible',
+  'error.home': 'Retour à la page d\'accueil',
+  'error.notFound': 'La page demandée n\'existe pas.',
+  'error.rateLimited': 'Vous avez envoyé trop de requêtes, veuillez patienter un instant et réessayer.',
+  'error.shuttingDown': 'Le service redémarre, veuillez réessayer dans quelques secondes.',
+  'error.jitsiUnreachable': 'Le service de réunion est actuellement injoignable, veuillez réessayer plus tard.',
+  'error.invalidState': 'La connexion n\'a pas pu aboutir, veuillez recommencer.',
+  'error.missingToken': 'Un jeton d\'accès est nécessaire pour rejoindre cette réunion.',
+  'error.invalidToken': 'Le jeton d\'accès est invalide ou a expiré.',
+  'error.invalidInvite': 'Le début ou la durée de l\'invitation est invalide.',
+  'error.invalidRoom': 'Ce nom de salle n\'est pas autorisé, veuillez en choisir un autre.',
+  'error.expiredForm': 'Le formulaire a expiré, veuillez recharger la page et réessayer.',
+  'error.linkTooLong': 'Le lien de cette salle est trop long pour un code QR.',
+  'errorId': 'Référence de l\'erreur',
+  'requestId': 'ID de la requête',
+  'maintenance.message': 'Les réunions sont actuellement indisponibles en raison d\'une maintenance planifiée. Veuillez réessayer plus tard.',
+};
+
+const es: Messages = {
+  'join': 'Unirse',
+  'landing.intro': 'Inicia sesión con la cuenta de tu organización para unirte a una reunión.',
+  'landing.room': 'Nombre de la sala',
+  'chooser.title': 'Iniciar sesión',
+  'chooser.intro': 'Elige cómo quieres iniciar sesión:',
+  'guest.title': 'Unirse como invitado',
+  'guest.intro': 'Introduce el código de {room} que recibiste con la invitación.',
+  'guest.name': 'Invitado',
+  'guest.passcode': 'Código',
+  'guest.invalid': 'El código no es correcto.',
+  'guest.captcha': 'Confirma que no eres un robot.',
+  'chooser.guest': 'Unirse como invitado con un código',
+  'rooms.title': 'Salas',
+  'rooms.empty': 'No hay salas disponibles para ti.',
+  'dialIn.title': 'Unirse por teléfono',
+  'dialIn.numbers': 'Llama a uno de estos números para unirte a {room}:',
+  'dialIn.pin': 'Introduce el PIN {pin} seguido de #.',
+  'dialIn.noPin': 'El PIN se solicita durante la llamada.',
+  'inviteMail.title': 'Invitar a personas',
+  'inviteMail.intro': 'Enviar una invitación a {room} por correo electrónico.',
+  'inviteMail.recipients': 'Direcciones de correo (separadas por comas o saltos de línea)',
+  'inviteMail.message': 'Mensaje personal (opcional)',
+  'inviteMail.send': 'Enviar invitaciones',
+  'inviteMail.empty': 'Introduce al menos una dirección de correo.',
+  'inviteMail.invalid': '{address} no es una dirección de correo válida.',
+  'inviteMail.tooMany': 'Se pueden invitar como máximo {max} personas a la vez.',
+  'inviteMail.failed': 'No se pudieron enviar las invitaciones, inténtalo de nuevo más tarde.',
+  'inviteMail.sent': 'La invitación a {room} se envió a {count} destinatarios.',
+  'inviteMail.subject': '{sender} te invita a {room}',
+  'inviteMail.body': '{sender} te invita a la reunión {room}. Únete con este enlace:',
+  'inviteMail.dialIn': 'Unirse por teléfono:',
+  'app.title': 'Unirse a la reunión',
+  'app.open': 'Abrir en la aplicación Jitsi Meet',
+  'app.browser': 'Continuar en el navegador',
+  'embed.title': 'Sesión iniciada',
+  'embed.message': 'Has iniciado sesión, puedes cerrar esta ventana.',
+  'embed.forbidden.title': 'Integración no permitida',
+  'embed.forbidden.message': 'El origen {origin} no puede integrar este servicio.',
+  'displayName.title': 'Tu nombre',
+  'displayName.intro': 'Este nombre se muestra a los demás participantes de {room}.',
+  'displayName.label': 'Nombre visible',
+  'displayName.length': 'El nombre debe tener entre {min} y {max} caracteres.',
+  'displayName.characters': 'El nombre contiene caracteres no permitidos.',
+  'welcome.schedule': 'Cuándo',
+  'welcome.organizer': 'Organizador',
+  'welcome.join': 'Unirse ahora',
+  'waiting.title': 'Esperando al anfitrión',
+  'waiting.message': 'La reunión {room} comienza en cuanto el anfitrión se haya unido. Esta página continúa automáticamente.',
+  'waiting.retry': 'Intentar de nuevo',
+  'expired.title': 'La sesión ha caducado',
+  'expired.retry': 'Volver a iniciar sesión',
+  'qr.caption': 'Escanea para unirte o abre',
+  'link.title': 'Enlace caducado',
+  'link.message': 'Este enlace no es válido o ha caducado, pide una nueva invitación.',
+  'link.landing': 'Introducir el nombre de la sala',
+  'idpError.title': 'Error al iniciar sesión',
+  'idpError.message': 'El proveedor de identidad no ha iniciado tu sesión.',
+  'idpError.reason': 'El proveedor de identidad no ha iniciado tu sesión:',
+  'idpError.code': 'Código de error',
+  'retry': 'Intentar de nuevo',
+  'error.title': 'Algo salió mal',
+  'error.message': 'No se ha podido completar tu solicitud.',
+  'error.400': 'Solicitud no válida',
+  'error.401': 'Sesión no iniciada',
+  'error.403': 'Acceso denegado',
+  'error.404': 'Página no encontrada',
+  'error.429': 'Demasiadas solicitudes',
+  'error.502': 'Servicio no disponible',
+  'error.503': 'No disponible temporalmente',
+  'error.home': 'Volver a la página de inicio',
+  'error.notFound': 'La página solicitada no existe.',
+  'error.rateLimited': 'Has enviado demasiadas solicitudes, espera un momento y vuelve a intentarlo.',
+  'error.shuttingDown': 'El servicio se está reiniciando, vuelve a intentarlo en unos segundos.',
+  'error.jitsiUnreachable': 'El servicio de reuniones no está disponible en este momento, vuelve a intentarlo más tarde.',
+  'error.invalidState': 'No se ha podido completar el inicio de sesión, vuelve a empezar.',
+  'error.missingToken': 'Se necesita un token de acceso para unirse a esta reunión.',
+  'error.invalidToken': 'El token de acceso no es válido o ha caducado.',
+  'error.invalidInvite': 'El inicio o la duración de la invitación no son válidos.',
+  'error.invalidRoom': 'Este nombre de sala no está permitido, elige otro.',
+  'error.expiredForm': 'El formulario ha caducado, recarga la página e inténtalo de nuevo.',
+  'error.linkTooLong': 'El enlace de esta sala es demasiado largo para un código QR.',
+  'errorId': 'ID del error',
+  'requestId': 'ID de la solicitud',
+  'maintenance.message': 'Las reuniones no están disponibles por un mantenimiento programado. Vuelve a intentarlo más tarde.',
+};
+
+const BUNDLED: Record<string, Messages> = {en, de, fr, es};
+
+// TRANSLATIONS replaces single messages of the bundled languages or adds languages
+const MESSAGES: Record<string, Messages> = Object.fromEntries(
+  [...new Set([...Object.keys(BUNDLED), ...Object.keys(TRANSLATIONS)])]
+    .map(language => [language, {...BUNDLED[language], ...TRANSLATIONS[language]}]),
+);
+
+const LANGUAGES = Object.keys(MESSAGES);
+
+export interface Translator {
+  language: string;
+  t(key: MessageKey, params?: Record<string, string | number>): string;
+}
+
+/**
+ * Selects the language remembered in the preferences or using the
+ * Accept-Language header, falling back to DEFAULT_LANGUAGE. Missing messages are taken from DEFAULT_LANGUAGE and
+ * english.
+ */
+export function translator(req: express.Request): Translator {
+  const preferred = getPreferences(req).language?.toLowerCase().split('-')[0];
+  const accepted = req.acceptsLanguages(LANGUAGES);
+  const language = preferred && LANGUAGES.includes(preferred)
+    ? preferred
+    : typeof accepted === 'string' && req.get('accept-language') ? accepted : DEFAULT_LANGUAGE;
+
+  return {
+    language,
+    t: (key, params = {}) => {
+      const message = MESSAGES[language]?.[key] ?? MESSAGES[DEFAULT_LANGUAGE]?.[key] ?? en[key];
+      return message.replace(/{(\w+)}/g, (placeholder, name: string) => name in params ? String(params[name]) : placeholder);
+    },
+  };
+}
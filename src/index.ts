@@ -1,45 +1,43 @@
-import * as express from 'express';
-import {sign as jwtSign} from 'jsonwebtoken';
-import {auth as openIdAuth} from 'express-openid-connect';
-
-const JITSI = 'jitsi';
-
-const PORT = process.env.PORT ?? 3000;
-const {JITSI_SECRET, JITSI_URL, JITSI_SUB} = process.env;
-
-if (!JITSI_SECRET || !JITSI_URL || !JITSI_SUB) {
-  console.error(`Missing environment variable JITSI_SECRET, JITSI_URL or JITSI_SUB.`);
-  process.exit(1);
-}
-
-const app = express();
-
-app.use(openIdAuth());
-
-function sign(name: string, email: string, id: string, allowedRoom: string): string {
-  return jwtSign({
-    context: {
-      user: {name, email, id}
-    },
-    aud: JITSI,
-    iss: JITSI,
-    sub: JITSI_SUB,
-    room: allowedRoom
-  }, JITSI_SECRET!);
-}
-
-app.get('/room/:room', (req, res) => {
-  if (!req.oidc.user) {
-    throw new Error('Missing user information.');
+import {CLI} from './cli';
+import {loadVaultSecrets} from './vault';
+import {resolveSecretReferences} from './secret-managers';
+
+async function main() {
+  if (CLI.command === 'print-config') {
+    const {exampleEnv, exampleToml} = await import(/* webpackMode: "eager" */ './config-reference');
+    process.stdout.write(CLI.options.FORMAT === 'toml' ? exampleToml() : exampleEnv());
+    return;
   }
 
-  const {sub, name, email} = req.oidc.user;
-  const token = sign(name, email, sub, "*");
+  // the configuration is only loaded after the secrets have been read from vault and secret managers
+  await resolveSecretReferences();
+  await loadVaultSecrets(async () => {
+    const {reloadConfig, SECRET} = await import(/* webpackMode: "eager" */ './config');
+    const log = await import(/* webpackMode: "eager" */ './log');
+    const problems = reloadConfig();
+
+    if (problems.length) {
+      log.error(`Secrets refreshed from vault were not applied: ${problems.join(' ')}`, {errorKind: 'vault'});
+    }
+
+    // replacing it would invalidate all sessions and cookies of logins in progress
+    if (process.env.SECRET !== SECRET) {
+      log.error('SECRET changed in vault, it is only applied after a restart.', {errorKind: 'vault'});
+    }
+  });
+
+  if (CLI.command === 'check') {
+    const {check} = await import(/* webpackMode: "eager" */ './check');
+    await check();
+    return;
+  }
 
-  const params = new URLSearchParams();
-  params.set('jwt', token);
+  const {serve} = await import(/* webpackMode: "eager" */ './server');
+  serve();
+}
 
-  res.redirect(`${JITSI_URL}/${req.params.room}?${params.toString()}`);
+// failures during the startup (config, secret managers, vault) end the process instead of an unhandled rejection
+main().catch(error => {
+  console.error(error);
+  process.exit(1);
 });
-
-app.listen(PORT, () => console.log(`Http Server is listening on port ${PORT}.`));
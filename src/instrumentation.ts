@@ -0,0 +1,38 @@
+import {monitorEventLoopDelay} from 'perf_hooks';
+import {Gauge} from './metrics';
+import * as log from './log';
+
+// replaced by webpack at build time, see RUNTIME_INSTRUMENTATION in webpack.config.js
+declare const BUILD_RUNTIME_INSTRUMENTATION: boolean;
+
+const INTERVAL = 10 * 1000;
+const STALL_THRESHOLD = 100;
+
+/**
+ * Monitors the delay of the event loop, which reveals handlers blocking it
+ * (e.g. synchronous work while a login is in flight). Only compiled into
+ * builds with RUNTIME_INSTRUMENTATION=true.
+ */
+export function instrumentRuntime() {
+  if (!BUILD_RUNTIME_INSTRUMENTATION) {
+    return;
+  }
+
+  const histogram = monitorEventLoopDelay({resolution: 10});
+  histogram.enable();
+
+  new Gauge('jitsi_openid_event_loop_delay_p99_seconds', 'P99 of the event loop delay since the last interval.', () => histogram.percentile(99) / 1e9);
+  new Gauge('jitsi_openid_event_loop_delay_max_seconds', 'Maximum event loop delay since the last interval.', () => histogram.max / 1e9);
+
+  setInterval(() => {
+    const max = histogram.max / 1e6;
+
+    if (max > STALL_THRESHOLD) {
+      log.warn(`Event loop was blocked for up to ${max.toFixed(0)}ms (p99 ${(histogram.percentile(99) / 1e6).toFixed(0)}ms).`);
+    }
+
+    histogram.reset();
+  }, INTERVAL).unref();
+
+  log.info('Runtime instrumentation is enabled.');
+}
@@ -0,0 +1,68 @@
+import * as express from 'express';
+import {ACCESS_TOKEN_HEADER} from './config';
+import {getClient} from './oidc';
+import {JitsiUser} from './jitsi';
+import {joinRoom, queryString} from './join';
+import {catchErrors, errorPage} from './errors';
+import {hostGroups} from './hosts';
+import {lacksParticipantRole} from './visitors';
+
+function accessToken(req: express.Request): string | undefined {
+  const forwarded = req.header(ACCESS_TOKEN_HEADER);
+
+  if (forwarded) {
+    return forwarded;
+  }
+
+  const [scheme, token] = req.header('authorization')?.split(' ') ?? [];
+  return scheme?.toLowerCase() === 'bearer' ? token : undefined;
+}
+
+/**
+ * Validates an opaque access token using token introspection. Claims missing
+ * in the introspection response are read from the userinfo endpoint.
+ */
+export async function introspect(token: string): Promise<JitsiUser | undefined> {
+  const client = await getClient();
+  const introspection = await client.introspect(token, 'access_token');
+
+  if (!introspection.active || !introspection.sub) {
+    return undefined;
+  }
+
+  let {name, email} = introspection as {name?: string, email?: string};
+
+  if (name === undefined || email === undefined) {
+    const userinfo = await client.userinfo(token);
+    name = name ?? userinfo.name;
+    email = email ?? userinfo.email;
+  }
+
+  return {
+    id: introspection.sub,
+    name: name ?? introspection.username ?? '',
+    email: email ?? '',
+    ...(lacksParticipantRole(introspection) ? {moderator: false, visitor: true} : {}),
+    ...hostGroups(introspection),
+  };
+}
+
+export const introspection = express.Router();
+
+introspection.get('/room/:room', catchErrors(async (req, res) => {
+  const token = accessToken(req);
+
+  if (!token) {
+    errorPage(req, res, 401, 'error.missingToken');
+    return;
+  }
+
+  const user = await introspect(token);
+
+  if (!user) {
+    errorPage(req, res, 401, 'error.invalidToken');
+    return;
+  }
+
+  await joinRoom(req, res, user, req.params.room, 'introspection', queryString(req));
+}));
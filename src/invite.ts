@@ -0,0 +1,199 @@
+import * as express from 'express';
+import {randomUUID} from 'crypto';
+import {readFileSync} from 'fs';
+import {BASE_URL, BODY_LIMIT, DIAL_IN_NUMBERS, INVITE_MAIL_TEMPLATE} from './config';
+import {formToken, isFormToken} from './cookies';
+import {withoutCompression} from './compression';
+import {catchErrors, errorPage} from './errors';
+import {escapeHtml, page} from './html';
+import {translator, Translator} from './i18n';
+import * as log from './log';
+import {sendMail} from './smtp';
+
+const MAX_RECIPIENTS = 20;
+const MAIL_TEMPLATE = INVITE_MAIL_TEMPLATE ? readFileSync(INVITE_MAIL_TEMPLATE, 'utf8') : undefined;
+// no whitespace or angle brackets, which would end up in the headers of the mail
+const EMAIL = /^[^\s<>@,;]+@[^\s<>@,;]+\.[^\s<>@,;]+$/;
+
+function escapeText(value: string): string {
+  return value.replace(/[\\;,]/g, char => `\\${char}`).replace(/\r?\n/g, '\\n');
+}
+
+// lines longer than 75 octets are folded (RFC 5545 3.1)
+function fold(line: string): string {
+  const chunks: string[] = [];
+  let rest = Buffer.from(line);
+
+  while (rest.length > 75) {
+    let end = chunks.length ? 74 : 75;
+
+    // don't split multi byte characters
+    while ((rest[end] & 0xc0) === 0x80) {
+      end--;
+    }
+
+    chunks.push(rest.slice(0, end).toString());
+    rest = rest.slice(end);
+  }
+
+  chunks.push(rest.toString());
+  return chunks.join('\r\n ');
+}
+
+function formatDate(date: Date): string {
+  return date.toISOString().replace(/[-:]|\.\d{3}/g, '');
+}
+
+function nextFullHour(): Date {
+  const date = new Date();
+  date.setUTCHours(date.getUTCHours() + 1, 0, 0, 0);
+  return date;
+}
+
+export const invite = express.Router();
+
+/**
+ * Calendar entry with the room link (and dial-in numbers), ?start=<iso date>,
+ * ?duration=<minutes> and ?title=<summary> are optional.
+ */
+invite.get('/room/:room/invite.ics', (req, res) => {
+  const {room} = req.params;
+  const {start, duration, title} = req.query;
+  const startDate = typeof start === 'string' ? new Date(start) : nextFullHour();
+  const minutes = typeof duration === 'string' ? Number(duration) : 60;
+
+  if (Number.isNaN(startDate.getTime()) || !Number.isInteger(minutes) || minutes <= 0) {
+    errorPage(req, res, 400, 'error.invalidInvite');
+    return;
+  }
+
+  const url = `${BASE_URL}/room/${encodeURIComponent(room)}`;
+  const description = [
+    `Join the meeting: ${url}`,
+    ...(DIAL_IN_NUMBERS.length ? ['', 'Join by phone:', ...DIAL_IN_NUMBERS] : []),
+  ].join('\n');
+
+  const lines = [
+    'BEGIN:VCALENDAR',
+    'VERSION:2.0',
+    'PRODID:-//jitsi-openid//invite//EN',
+    'METHOD:PUBLISH',
+    'BEGIN:VEVENT',
+    `UID:${randomUUID()}@${new URL(BASE_URL).hostname}`,
+    `DTSTAMP:${formatDate(new Date())}`,
+    `DTSTART:${formatDate(startDate)}`,
+    `DTEND:${formatDate(new Date(startDate.getTime() + minutes * 60 * 1000))}`,
+    `SUMMARY:${escapeText(typeof title === 'string' && title ? title : room)}`,
+    `LOCATION:${escapeText(url)}`,
+    `URL:${url}`,
+    `DESCRIPTION:${escapeText(description)}`,
+    'END:VEVENT',
+    'END:VCALENDAR',
+  ];
+
+  res.type('text/calendar')
+    .attachment(`${room}.ics`)
+    .send(`${lines.map(fold).join('\r\n')}\r\n`);
+});
+
+function formScope(req: express.Request): string {
+  return `invite:${req.oidc.user?.sub}:${req.params.room}`;
+}
+
+function mailForm({t, language}: Translator, room: string, token: string, recipients = '', message = '', notice?: string): string {
+  return page(t('inviteMail.title'), `
+    <p>${escapeHtml(t('inviteMail.intro', {room}))}</p>
+    ${notice ? `<p role="alert">${escapeHtml(notice)}</p>` : ''}
+    <form method="post">
+      <input type="hidden" name="token" value="${escapeHtml(token)}">
+      <label for="recipients">${escapeHtml(t('inviteMail.recipients'))}</label>
+      <textarea id="recipients" name="recipients" rows="4" required autofocus>${escapeHtml(recipients)}</textarea>
+      <label for="message">${escapeHtml(t('inviteMail.message'))}</label>
+      <textarea id="message" name="message" rows="4">${escapeHtml(message)}</textarea>
+      <button type="submit">${escapeHtml(t('inviteMail.send'))}</button>
+    </form>
+  `, language);
+}
+
+function mailText({t}: Translator, room: string, link: string, sender: string, message: string): string {
+  if (MAIL_TEMPLATE) {
+    const values: Record<string, string> = {room, link, sender, message};
+    return MAIL_TEMPLATE.replace(/{{(\w+)}}/g, (placeholder, name: string) => values[name] ?? placeholder);
+  }
+
+  return [
+    t('inviteMail.body', {sender, room}),
+    '',
+    link,
+    ...(message ? ['', message] : []),
+    ...(DIAL_IN_NUMBERS.length ? ['', t('inviteMail.dialIn'), ...DIAL_IN_NUMBERS] : []),
+  ].join('\n');
+}
+
+/**
+ * Lets logged in users invite people to a room by mail (requires SMTP_URL),
+ * mounted behind the login of the providers.
+ */
+export const mailInvite = express.Router();
+
+// the form token is sent together with the room name of the url
+mailInvite.get('/room/:room/invite', (req, res) => {
+  withoutCompression(res);
+  res.send(mailForm(translator(req), req.params.room, formToken(formScope(req))));
+});
+
+mailInvite.post('/room/:room/invite', express.urlencoded({extended: false, limit: BODY_LIMIT}), catchErrors(async (req, res) => {
+  const messages = translator(req);
+  const {room} = req.params;
+  const token = formToken(formScope(req));
+  withoutCompression(res);
+
+  // the session cookie alone isn't enough, other sites could submit the form
+  if (!isFormToken(formScope(req), req.body?.token)) {
+    errorPage(req, res, 403, 'error.expiredForm');
+    return;
+  }
+
+  const recipients = typeof req.body?.recipients === 'string' ? req.body.recipients : '';
+  const message = typeof req.body?.message === 'string' ? req.body.message.trim() : '';
+  const addresses = [...new Set(recipients.split(/[\s,;]+/).filter(Boolean))] as string[];
+  const invalidAddress = addresses.find(address => !EMAIL.test(address));
+
+  let problem: string | undefined;
+
+  if (!addresses.length) {
+    problem = messages.t('inviteMail.empty');
+  }
+  else if (invalidAddress) {
+    problem = messages.t('inviteMail.invalid', {address: invalidAddress});
+  }
+  else if (addresses.length > MAX_RECIPIENTS) {
+    problem = messages.t('inviteMail.tooMany', {max: MAX_RECIPIENTS});
+  }
+
+  if (problem) {
+    res.status(400).send(mailForm(messages, room, token, recipients, message, problem));
+    return;
+  }
+
+  const {sub, name, email} = req.oidc.user ?? {};
+  const sender = name ?? email ?? sub;
+  const link = `${BASE_URL}/room/${encodeURIComponent(room)}`;
+
+  try {
+    await sendMail({
+      to: addresses,
+      subject: messages.t('inviteMail.subject', {sender, room}),
+      text: mailText(messages, room, link, sender, message),
+      ...(typeof email === 'string' && EMAIL.test(email) ? {replyTo: email} : {}),
+    });
+  }
+  catch (error) {
+    log.warn(`Sending invitations to ${room} failed: ${error.message}`, {room, errorKind: 'smtp'});
+    res.status(502).send(mailForm(messages, room, token, recipients, message, messages.t('inviteMail.failed')));
+    return;
+  }
+
+  log.info(`[audit] ${sub} invited ${addresses.length} recipients to room ${room} by mail`, {room});
+  res.send(mailForm(messages, room, token, '', '', messages.t('inviteMail.sent', {room, count: addresses.length})));
+}));
@@ -0,0 +1,66 @@
+import {sign as jwtSign} from 'jsonwebtoken';
+import {JITSI_BACKENDS, JITSI_SECRET, JITSI_SUB, JITSI_TOKEN_LIFETIME, JITSI_URL, JitsiBackend} from './config';
+
+const JITSI = 'jitsi';
+
+export interface JitsiUser {
+  id: string;
+  name: string;
+  email: string;
+  // sets the affiliation of the user if the token_affiliation module of prosody is enabled
+  moderator?: boolean;
+  // joins with reduced privileges using the visitors feature of jitsi
+  visitor?: boolean;
+  // groups of ROOM_DIRECTORY_CLAIM, deciding which rooms the user hosts (not part of the token)
+  groups?: string[];
+}
+
+/**
+ * Selects the jitsi deployment of a room, by the host the request was sent to
+ * or the prefix of the room, falling back to JITSI_URL.
+ */
+export function jitsiBackend(room: string, host?: string): JitsiBackend {
+  const backends = Object.values(JITSI_BACKENDS);
+
+  return backends.find(backend => backend.host && backend.host === host)
+    ?? backends.find(backend => backend.prefix && room.startsWith(backend.prefix))
+    ?? {url: JITSI_URL, sub: JITSI_SUB, secret: JITSI_SECRET};
+}
+
+/**
+ * Issues a token expiring after JITSI_TOKEN_LIFETIME, prosody checks the
+ * expiry when joining, running meetings aren't ended.
+ */
+export function sign(user: JitsiUser, allowedRoom: string, backend: JitsiBackend): string {
+  return jwtSign({
+    context: {
+      user: {name: user.name, email: user.email, id: user.id, ...(user.moderator !== undefined ? {moderator: user.moderator} : {})},
+      ...(user.visitor ? {visitor: true} : {}),
+    },
+    aud: JITSI,
+    iss: JITSI,
+    sub: backend.sub,
+    room: allowedRoom
+  }, backend.secret.bytes(), JITSI_TOKEN_LIFETIME ? {expiresIn: JITSI_TOKEN_LIFETIME} : {});
+}
+
+/**
+ * Builds the url of the room, query parameters of the original link (e.g.
+ * lang=de) are passed along. Overrides of the jitsi config (e.g.
+ * userInfo.displayName) are passed in the fragment.
+ */
+export function roomUrl(room: string, token: string, backend: JitsiBackend, query = '', overrides: Record<string, string> = {}): string {
+  const params = new URLSearchParams(query);
+  params.set('jwt', token);
+
+  const fragment = Object.entries(overrides)
+    .map(([key, value]) => `${key}=${encodeURIComponent(JSON.stringify(value))}`)
+    .join('&');
+
+  return `${backend.url}/${room}?${params.toString()}${fragment ? `#${fragment}` : ''}`;
+}
+
+// the jitsi meet apps handle links like org.jitsi.meet://meet.example.com/room?jwt=...
+export function appUrl(url: string): string {
+  return url.replace(/^https?:\/\//, 'org.jitsi.meet://');
+}
@@ -0,0 +1,350 @@
+import * as express from 'express';
+import {
+  BASE_PATH,
+  BASE_URL,
+  BODY_LIMIT,
+  DISPLAY_NAME_CONFIRM,
+  DISPLAY_NAME_MAX_LENGTH,
+  DISPLAY_NAME_MIN_LENGTH,
+  DISPLAY_NAME_PATTERN,
+  DirectoryRoom,
+  EMBED_ORIGINS,
+  JITSI_PROXY_UPSTREAM,
+  JITSI_REDIRECT_STATUS,
+  MOBILE_DEEP_LINK,
+  ROOM_NAME_PATTERN,
+  WELCOME_PAGE,
+} from './config';
+import {getCookie, seal, transientCookie, unseal} from './cookies';
+import {directoryRoom} from './directory';
+import {escapeHtml, page, scriptJson} from './html';
+import {translator, Translator} from './i18n';
+import {appUrl, jitsiBackend, JitsiUser, roomUrl, sign} from './jitsi';
+import {withoutCompression} from './compression';
+import * as log from './log';
+import {recordJoin} from './logins';
+import {tokenIssued} from './metrics';
+import {auditToken} from './audit';
+import {fitsQr, qrSvg} from './qr';
+import {TOKEN_COOKIE} from './proxy';
+import {errorPage} from './errors';
+import {isCrowded} from './visitors';
+import {getPreferences, preferredName, savePreferences} from './preferences';
+import {hasHost, hostJoined, isHost, requiresHost} from './hosts';
+
+const PENDING_COOKIE = 'pending_join';
+const MOBILE_USER_AGENT = /Android|iPhone|iPad|iPod/i;
+
+interface PendingJoin {
+  user: JitsiUser;
+  room: string;
+  flow: string;
+  query: string;
+  // portal receiving the token, if the join continues after waiting for the host
+  origin?: string;
+}
+
+/**
+ * Returns the query string of the request, without the leading question mark.
+ */
+export function queryString(req: express.Request): string {
+  const index = req.originalUrl.indexOf('?');
+  return index === -1 ? '' : req.originalUrl.slice(index + 1);
+}
+
+type Delivery = 'redirect' | 'json' | 'embed';
+
+function redirectToRoom(req: express.Request, res: express.Response, {user, room, flow, query}: PendingJoin, delivery: Delivery = 'redirect', origin = '', displayName?: string) {
+  if (requiresHost(room) && !isHost(user, room) && !hasHost(room)) {
+    if (delivery === 'json') {
+      res.status(409).json({error: 'waiting_for_host'});
+      return;
+    }
+
+    res.cookie(PENDING_COOKIE, seal({user, room, flow, query, origin}), transientCookie(req));
+    res.send(waitingPage(translator(req), displayName ?? user.name, room));
+    return;
+  }
+
+  if (isHost(user, room)) {
+    hostJoined(room);
+  }
+
+  const backend = jitsiBackend(room, req.hostname);
+  // guests only receive a token for the room of the passcode
+  const allowedRoom = flow === 'guest' ? room : '*';
+  const token = sign(user, allowedRoom, backend);
+
+  tokenIssued(flow, room, allowedRoom);
+  auditToken(req, user, room, flow, token);
+  recordJoin(user.id, user.name, room, flow);
+  log.annotate({room});
+  log.debug(`Issued token for ${user.id}`);
+
+  // an explicit ?lang= of the link takes precedence over the remembered language
+  const language = new URLSearchParams(query).has('lang') ? undefined : getPreferences(req).language;
+  const url = roomUrl(room, token, backend, query, {
+    ...(displayName ? {'userInfo.displayName': displayName} : {}),
+    ...(language ? {'config.defaultLanguage': language} : {}),
+  });
+
+  withoutCompression(res);
+
+  if (delivery === 'json') {
+    res.json({jwt: token, url});
+    return;
+  }
+
+  if (delivery === 'embed') {
+    res.send(embedPage(translator(req), origin, {type: 'jitsi-openid:token', room, jwt: token, url}));
+    return;
+  }
+
+  if (JITSI_PROXY_UPSTREAM) {
+    res.cookie(TOKEN_COOKIE, token, {...transientCookie(req), maxAge: 60 * 1000});
+    res.redirect(JITSI_REDIRECT_STATUS, `${BASE_PATH}/${encodeURIComponent(room)}${query ? `?${query}` : ''}`);
+    return;
+  }
+
+  if (MOBILE_DEEP_LINK && MOBILE_USER_AGENT.test(req.get('user-agent') ?? '')) {
+    res.send(appPage(translator(req), url));
+    return;
+  }
+
+  res.redirect(JITSI_REDIRECT_STATUS, url);
+}
+
+/**
+ * Opens the jitsi meet app, if it isn't installed the user continues in the
+ * browser using the link.
+ */
+function appPage({t, language}: Translator, url: string): string {
+  return page(t('app.title'), `
+    <meta http-equiv="refresh" content="0; url=${escapeHtml(appUrl(url))}">
+    <p><a href="${escapeHtml(appUrl(url))}">${escapeHtml(t('app.open'))}</a></p>
+    <p><a href="${escapeHtml(url)}">${escapeHtml(t('app.browser'))}</a></p>
+  `, language);
+}
+
+/**
+ * Hands the token to the portal embedding jitsi using the iframe api, which
+ * opened this page as popup or iframe.
+ */
+function embedPage({t, language}: Translator, origin: string, message: Record<string, string>): string {
+  return page(t('embed.title'), `
+    <p>${escapeHtml(t('embed.message'))}</p>
+    <script>
+      (window.opener || window.parent).postMessage(${scriptJson(message)}, ${scriptJson(origin)});
+      if (window.opener) window.close();
+    </script>
+  `, language);
+}
+
+/**
+ * Shown until a host of the room joined, polls the host status and then
+ * continues the join with the pending cookie.
+ */
+function waitingPage({t, language}: Translator, name: string, room: string): string {
+  return page(t('waiting.title'), `
+    <p>${escapeHtml(t('waiting.message', {room}))}</p>
+    <form id="waiting" action="${escapeHtml(`${BASE_PATH}/display-name`)}" method="post">
+      <input type="hidden" name="name" value="${escapeHtml(name)}">
+      <button type="submit">${escapeHtml(t('waiting.retry'))}</button>
+    </form>
+    <script>
+      setInterval(function () {
+        fetch(${scriptJson(`${BASE_PATH}/room/${encodeURIComponent(room)}/host`)})
+          .then(function (response) { return response.json(); })
+          .then(function (status) { if (status.present) document.getElementById('waiting').submit(); });
+      }, 5000);
+    </script>
+  `, language);
+}
+
+function welcomeRoom(room: string): DirectoryRoom | undefined {
+  return WELCOME_PAGE ? directoryRoom(room) : undefined;
+}
+
+/**
+ * Page shown before joining: the welcome page of rooms in ROOM_DIRECTORY
+ * and/or the form to confirm the display name.
+ */
+function displayNamePage({t, language}: Translator, name: string, room: string, problem?: string): string {
+  const welcome = welcomeRoom(room);
+  const details = [
+    welcome?.schedule ? `<dt>${escapeHtml(t('welcome.schedule'))}</dt><dd>${escapeHtml(welcome.schedule)}</dd>` : '',
+    welcome?.organizer ? `<dt>${escapeHtml(t('welcome.organizer'))}</dt><dd>${escapeHtml(welcome.organizer)}</dd>` : '',
+  ].join('');
+
+  return page(welcome ? welcome.label ?? room : t('displayName.title'), `
+    ${welcome?.description ? `<p>${escapeHtml(welcome.description)}</p>` : ''}
+    ${details ? `<dl>${details}</dl>` : ''}
+    ${DISPLAY_NAME_CONFIRM ? `<p>${escapeHtml(t('displayName.intro', {room}))}</p>` : ''}
+    ${problem ? `<p role="alert">${escapeHtml(problem)}</p>` : ''}
+    <form action="${escapeHtml(`${BASE_PATH}/display-name`)}" method="post">
+      ${DISPLAY_NAME_CONFIRM ? `
+        <label for="name">${escapeHtml(t('displayName.label'))}</label>
+        <input id="name" name="name" value="${escapeHtml(name)}" minlength="${DISPLAY_NAME_MIN_LENGTH}" maxlength="${DISPLAY_NAME_MAX_LENGTH}" required autofocus>
+      ` : ''}
+      <button type="submit">${escapeHtml(t(welcome ? 'welcome.join' : 'join'))}</button>
+    </form>
+  `, language);
+}
+
+export function validateDisplayName({t}: Translator, name: string): string | undefined {
+  if (name.length < DISPLAY_NAME_MIN_LENGTH || name.length > DISPLAY_NAME_MAX_LENGTH) {
+    return t('displayName.length', {min: DISPLAY_NAME_MIN_LENGTH, max: DISPLAY_NAME_MAX_LENGTH});
+  }
+
+  if (DISPLAY_NAME_PATTERN && !DISPLAY_NAME_PATTERN.test(name)) {
+    return t('displayName.characters');
+  }
+
+  return undefined;
+}
+
+/**
+ * Completes a login by redirecting to the room with a signed token. If the
+ * display name has to be confirmed or the room has a welcome page, the user is
+ * kept in a sealed cookie and the page is shown instead. Users beyond
+ * VISITOR_THRESHOLD participants join as visitors, hosts of the room as
+ * moderators, everyone else waits for them in rooms requiring a host. Scripts and single page apps
+ * receive the token as JSON using ?format=json or Accept: application/json.
+ */
+export async function joinRoom(req: express.Request, res: express.Response, user: JitsiUser, room: string, flow: string, query: string) {
+  if (isHost(user, room)) {
+    user = {...user, moderator: true, visitor: false};
+  }
+  else if (!user.visitor && await isCrowded(room)) {
+    user = {...user, moderator: false, visitor: true};
+  }
+
+  const params = new URLSearchParams(query);
+  const format = params.get('format');
+  const origin = params.get('origin') ?? '';
+  params.delete('format');
+  params.delete('origin');
+  query = params.toString();
+
+  // the language chosen for jitsi (?lang=de) is remembered for the next joins and the pages of this service
+  const lang = params.get('lang');
+
+  if (lang && /^[a-zA-Z-]{2,10}$/.test(lang)) {
+    savePreferences(req, res, {language: lang});
+  }
+
+  const preferred = preferredName(req, user.id);
+
+  if (format === 'embed') {
+    if (!EMBED_ORIGINS.includes(origin)) {
+      const {t, language} = translator(req);
+      res.status(400).send(page(t('embed.forbidden.title'), `<p>${escapeHtml(t('embed.forbidden.message', {origin}))}</p>`, language));
+      return;
+    }
+
+    redirectToRoom(req, res, {user, room, flow, query}, 'embed', origin, preferred);
+    return;
+  }
+
+  if (format === 'json' || req.accepts(['html', 'json']) === 'json') {
+    redirectToRoom(req, res, {user, room, flow, query}, 'json', '', preferred);
+    return;
+  }
+
+  if (!DISPLAY_NAME_CONFIRM && !welcomeRoom(room)) {
+    redirectToRoom(req, res, {user, room, flow, query}, 'redirect', '', preferred);
+    return;
+  }
+
+  res.cookie(PENDING_COOKIE, seal({user, room, flow, query}), transientCookie(req));
+  res.send(displayNamePage(translator(req), preferred ?? user.name, room));
+}
+
+export function isValidRoom(room: string): boolean {
+  return !ROOM_NAME_PATTERN || ROOM_NAME_PATTERN.test(room);
+}
+
+/**
+ * Rejects rooms not matching ROOM_NAME_PATTERN before the login is started.
+ */
+export function roomPolicy(req: express.Request, res: express.Response, next: express.NextFunction) {
+  if (!isValidRoom(req.params.room)) {
+    errorPage(req, res, 400, 'error.invalidRoom');
+    return;
+  }
+
+  next();
+}
+
+export const joinFlow = express.Router();
+
+/**
+ * Links shared from a breakout room are turned into a link of the main room,
+ * the breakout id is passed through the login as query parameter.
+ */
+joinFlow.get('/room/:room/breakout/:breakout', (req, res) => {
+  const params = new URLSearchParams(queryString(req));
+  params.set('breakout', req.params.breakout);
+
+  res.redirect(`${req.baseUrl}/room/${encodeURIComponent(req.params.room)}?${params.toString()}`);
+});
+
+/**
+ * Shows a QR code of the room link (which starts the login) on meeting room
+ * displays, so people can join from their phones.
+ */
+joinFlow.get('/room/:room/qr', (req, res) => {
+  const url = `${BASE_URL}/room/${encodeURIComponent(req.params.room)}`;
+  const {t, language} = translator(req);
+
+  if (!fitsQr(url)) {
+    errorPage(req, res, 414, 'error.linkTooLong');
+    return;
+  }
+
+  res.send(page(req.params.room, `
+    <figure>
+      ${qrSvg(url)}
+      <figcaption>${escapeHtml(t('qr.caption'))} <a href="${escapeHtml(url)}">${escapeHtml(url)}</a></figcaption>
+    </figure>
+  `, language));
+});
+
+/**
+ * Polled by the waiting page of rooms requiring a host.
+ */
+joinFlow.get('/room/:room/host', (req, res) => {
+  res.json({present: !requiresHost(req.params.room) || hasHost(req.params.room)});
+});
+
+joinFlow.post('/display-name', express.urlencoded({extended: false, limit: BODY_LIMIT}), (req, res) => {
+  const pending = unseal<PendingJoin>(getCookie(req, PENDING_COOKIE));
+  const messages = translator(req);
+
+  if (!pending) {
+    res.status(400).send(page(messages.t('expired.title'), `<p><a href="${escapeHtml(`${BASE_PATH}/`)}">${escapeHtml(messages.t('expired.retry'))}</a></p>`, messages.language));
+    return;
+  }
+
+  const delivery = pending.origin ? 'embed' : 'redirect';
+
+  // the welcome and waiting pages only confirm joining the room
+  if (!DISPLAY_NAME_CONFIRM) {
+    res.clearCookie(PENDING_COOKIE, transientCookie(req));
+    redirectToRoom(req, res, pending, delivery, pending.origin, preferredName(req, pending.user.id));
+    return;
+  }
+
+  const name = typeof req.body?.name === 'string' ? req.body.name.trim().replace(/\s+/g, ' ') : '';
+  const problem = validateDisplayName(messages, name);
+
+  // the pending join is kept, so the corrected name can be submitted again
+  if (problem) {
+    res.status(400).send(displayNamePage(messages, name, pending.room, problem));
+    return;
+  }
+
+  res.clearCookie(PENDING_COOKIE, transientCookie(req));
+  savePreferences(req, res, {user: pending.user.id, name});
+  redirectToRoom(req, res, {...pending, user: {...pending.user, name}}, delivery, pending.origin);
+});
@@ -0,0 +1,62 @@
+import * as express from 'express';
+import {existsSync} from 'fs';
+import {join} from 'path';
+import {BASE_PATH, STATIC_DIR} from './config';
+import {escapeHtml, page} from './html';
+import {MessageKey, translator} from './i18n';
+import {isValidRoom} from './join';
+
+export const landing = express.Router();
+
+function joinForm(req: express.Request, room = '', problem?: MessageKey): string {
+  const {t, language} = translator(req);
+
+  return page('Jitsi Meet', `
+    <p>${escapeHtml(t('landing.intro'))}</p>
+    ${problem ? `<p role="alert">${escapeHtml(t(problem))}</p>` : ''}
+    <form action="${escapeHtml(`${BASE_PATH}/join`)}" method="get">
+      <label for="room">${escapeHtml(t('landing.room'))}</label>
+      <input id="room" name="room" value="${escapeHtml(room)}" required autofocus>
+      <button type="submit">${escapeHtml(t('join'))}</button>
+    </form>
+  `, language);
+}
+
+landing.get('/', (req, res) => {
+  res.send(joinForm(req));
+});
+
+/**
+ * Target of the form, which is shown again if the room name is empty or
+ * doesn't match ROOM_NAME_PATTERN. Valid names continue with the login.
+ */
+landing.get('/join', (req, res) => {
+  const room = typeof req.query.room === 'string' ? req.query.room.trim() : '';
+
+  if (!room) {
+    res.send(joinForm(req));
+    return;
+  }
+
+  if (!isValidRoom(room)) {
+    res.status(400).send(joinForm(req, room, 'error.invalidRoom'));
+    return;
+  }
+
+  res.redirect(`${BASE_PATH}/room/${encodeURIComponent(room)}`);
+});
+
+landing.get('/favicon.ico', (req, res) => {
+  const favicon = STATIC_DIR && join(STATIC_DIR, 'favicon.ico');
+
+  if (favicon && existsSync(favicon)) {
+    res.sendFile(favicon, {maxAge: '1d'});
+    return;
+  }
+
+  res.status(204).end();
+});
+
+landing.get('/robots.txt', (req, res) => {
+  res.type('text/plain').send('User-agent: *\nDisallow: /\n');
+});
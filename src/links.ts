@@ -0,0 +1,74 @@
+import * as express from 'express';
+import {randomBytes} from 'crypto';
+import {BASE_PATH, BASE_URL} from './config';
+import {escapeHtml, page} from './html';
+import {translator} from './i18n';
+
+// without characters that are easily confused (0/o, 1/l)
+const ALPHABET = 'abcdefghijkmnpqrstuvwxyz23456789';
+const SLUG_LENGTH = 6;
+
+export interface ShortLink {
+  room: string;
+  query: string;
+  client: string;
+  expires: number;
+}
+
+const links = new Map<string, ShortLink>();
+
+setInterval(() => {
+  const now = Date.now();
+
+  for (const [slug, link] of links) {
+    if (link.expires <= now) {
+      links.delete(slug);
+    }
+  }
+}, 60 * 1000).unref();
+
+function slug(): string {
+  return [...randomBytes(SLUG_LENGTH)].map(byte => ALPHABET[byte % ALPHABET.length]).join('');
+}
+
+/**
+ * Mints a short link to a room, the links are kept in memory and don't
+ * survive a restart.
+ */
+export function createLink(link: ShortLink): {slug: string, url: string} {
+  let id = slug();
+
+  while (links.has(id)) {
+    id = slug();
+  }
+
+  links.set(id, link);
+
+  return {slug: id, url: `${BASE_URL}/j/${id}`};
+}
+
+export function revokeLink(id: string, client: string): boolean {
+  if (links.get(id)?.client !== client) {
+    return false;
+  }
+
+  return links.delete(id);
+}
+
+export const shortLinks = express.Router();
+
+shortLinks.get('/j/:slug', (req, res) => {
+  const link = links.get(req.params.slug);
+
+  if (!link || link.expires <= Date.now()) {
+    const {t, language} = translator(req);
+
+    res.status(404).send(page(t('link.title'), `
+      <p>${escapeHtml(t('link.message'))}</p>
+      <p><a href="${escapeHtml(`${BASE_PATH}/`)}">${escapeHtml(t('link.landing'))}</a></p>
+    `, language));
+    return;
+  }
+
+  res.redirect(`${BASE_PATH}/room/${encodeURIComponent(link.room)}${link.query ? `?${link.query}` : ''}`);
+});
@@ -0,0 +1,86 @@
+import * as net from 'net';
+import {existsSync, lstatSync, unlinkSync} from 'fs';
+
+export type ListenAddress = {path: string} | {host?: string, port: number} | {fd: number};
+
+// first file descriptor passed by systemd socket activation
+const SD_LISTEN_FDS_START = 3;
+
+/**
+ * Parses "unix:/path/to.sock", "host:port", "[::]:port" or a plain port.
+ */
+export function parseAddress(address: string): ListenAddress {
+  if (address.startsWith('unix:')) {
+    return {path: address.slice('unix:'.length)};
+  }
+
+  const match = address.match(/^(?:\[([^\]]+)]|([^:]+)):(\d+)$/) ?? address.match(/^()()(\d+)$/);
+
+  if (!match) {
+    throw new Error(`Invalid listen address ${address}.`);
+  }
+
+  return {host: match[1] || match[2] || undefined, port: Number(match[3])};
+}
+
+export function formatAddress(address: ListenAddress): string {
+  if ('fd' in address) {
+    return `systemd socket (fd ${address.fd})`;
+  }
+
+  if ('path' in address) {
+    return `unix:${address.path}`;
+  }
+
+  return address.host ? `${address.host.includes(':') ? `[${address.host}]` : address.host}:${address.port}` : `port ${address.port}`;
+}
+
+/**
+ * Returns the sockets passed by systemd (LISTEN_FDS), if the process was
+ * started using socket activation.
+ */
+export function systemdAddresses(): ListenAddress[] | undefined {
+  const count = Number(process.env.LISTEN_FDS);
+
+  if (Number(process.env.LISTEN_PID) !== process.pid || !count) {
+    return undefined;
+  }
+
+  return Array.from({length: count}, (_, index) => ({fd: SD_LISTEN_FDS_START + index}));
+}
+
+export function parseAddresses(addresses: string): ListenAddress[] {
+  return addresses.split(',').map(address => address.trim()).filter(Boolean).map(parseAddress);
+}
+
+/**
+ * An IPv6 socket also accepts IPv4 connections by default, which conflicts
+ * with an additional IPv4 address on the same port.
+ */
+function ipv6Only(address: {host?: string, port: number}, addresses: ListenAddress[]): boolean {
+  return !!address.host?.includes(':')
+    && addresses.some(other => other !== address && 'port' in other && other.port === address.port && !other.host?.includes(':'));
+}
+
+export function listen(server: net.Server, address: ListenAddress, addresses: ListenAddress[], callback: () => void) {
+  if ('fd' in address) {
+    server.listen({fd: address.fd}, callback);
+    return;
+  }
+
+  if ('path' in address) {
+    // a socket left over by a previous (crashed) process would make listen fail, other files are never removed
+    if (existsSync(address.path)) {
+      if (!lstatSync(address.path).isSocket()) {
+        throw new Error(`Unable to listen on ${address.path}, the file exists and is not a socket.`);
+      }
+
+      unlinkSync(address.path);
+    }
+
+    server.listen(address.path, callback);
+    return;
+  }
+
+  server.listen({port: address.port, host: address.host, ipv6Only: ipv6Only(address, addresses)}, callback);
+}
@@ -0,0 +1,104 @@
+import {AsyncLocalStorage} from 'async_hooks';
+
+export type Level = 'debug' | 'info' | 'warn' | 'error';
+export type Fields = Record<string, string | number | boolean | undefined>;
+export type Sink = (level: Level, line: string) => void;
+
+const LEVELS: Level[] = ['debug', 'info', 'warn', 'error'];
+
+// tokens, authorization codes and cookies are never written, e.g. the query of a failed callback or of the redirect to jitsi
+const SECRETS: Array<[RegExp, string]> = [
+  [/\beyJ[\w-]*\.[\w-]*\.[\w-]*/g, '[redacted]'],
+  [/([?&#;](?:code|id_token|access_token|refresh_token|jwt|token|device_code|passcode|client_secret)=)[^&#\s"']*/gi, '$1[redacted]'],
+  [/(\b(?:bearer|basic)\s+)[\w\-.~+/]+=*/gi, '$1[redacted]'],
+  [/(\b(?:set-)?cookie:\s*)[^\n"]*/gi, '$1[redacted]'],
+];
+
+const context = new AsyncLocalStorage<Fields>();
+
+let json = false;
+let minimum = LEVELS.indexOf('info');
+let sink: Sink | undefined;
+
+export function isLevel(value: string): value is Level {
+  return (LEVELS as string[]).includes(value);
+}
+
+/**
+ * Applies LOG_FORMAT and LOG_LEVEL, logging is independent of the config
+ * module, because it is already used while loading secrets. Without a sink
+ * the lines are written to stdout and stderr.
+ */
+export function configureLogging(format: string, level: Level, output?: Sink) {
+  json = format === 'json';
+  minimum = LEVELS.indexOf(level);
+  sink = output ?? sink;
+}
+
+/**
+ * Runs the callback (and everything it starts asynchronously) in the context
+ * of a request, so log lines are correlated using the request id.
+ */
+export function withRequestId<T>(requestId: string, callback: () => T): T {
+  return context.run({requestId}, callback);
+}
+
+export function currentRequestId(): string | undefined {
+  return context.getStore()?.requestId as string | undefined;
+}
+
+/**
+ * Adds fields (e.g. the room) to all following log lines of the current request.
+ */
+export function annotate(fields: Fields) {
+  Object.assign(context.getStore() ?? {}, fields);
+}
+
+export function redact(text: string): string {
+  return SECRETS.reduce((result, [pattern, replacement]) => result.replace(pattern, replacement), text);
+}
+
+function formatJson(level: Level, message: string, fields: Fields): string {
+  return JSON.stringify({time: new Date().toISOString(), level, message, ...context.getStore(), ...fields});
+}
+
+function formatText(message: string, fields: Fields): string {
+  const {requestId, ...rest} = {...context.getStore(), ...fields};
+  const details = Object.entries(rest)
+    .filter(([, value]) => value !== undefined)
+    .map(([key, value]) => `${key}=${value}`)
+    .join(' ');
+
+  return `${requestId ? `[${requestId}] ` : ''}${message}${details ? ` ${details}` : ''}`;
+}
+
+function write(level: Level, message: string, fields: Fields) {
+  if (LEVELS.indexOf(level) < minimum) {
+    return;
+  }
+
+  const line = redact(json ? formatJson(level, message, fields) : formatText(message, fields));
+
+  if (sink) {
+    sink(level, line);
+    return;
+  }
+
+  (level === 'error' || level === 'warn' ? process.stderr : process.stdout).write(`${line}\n`);
+}
+
+export function debug(message: string, fields: Fields = {}) {
+  write('debug', message, fields);
+}
+
+export function info(message: string, fields: Fields = {}) {
+  write('info', message, fields);
+}
+
+export function warn(message: string, fields: Fields = {}) {
+  write('warn', message, fields);
+}
+
+export function error(message: string, fields: Fields = {}) {
+  write('error', message, fields);
+}
@@ -0,0 +1,137 @@
+import * as express from 'express';
+import {randomBytes} from 'crypto';
+import {Gauge, loginAge, loginsCompleted, loginsFailed, loginsStarted} from './metrics';
+import * as log from './log';
+import {audit} from './audit';
+
+// logins which haven't returned after this time are considered abandoned
+const LOGIN_TIMEOUT = 10 * 60 * 1000;
+// number of joins kept for the admin api and the dashboard
+const RECENT_JOINS = 50;
+// rooms without joins for this time are forgotten
+const ROOM_RETENTION = 24 * 60 * 60 * 1000;
+// the error of the callback is set by the client, other values are reported as other
+const FAILURE_REASONS = new Set([
+  'access_denied', 'login_required', 'consent_required', 'interaction_required', 'invalid_state', 'token_exchange',
+]);
+
+export interface PendingLogin {
+  id: string;
+  provider: string;
+  started: number;
+}
+
+export interface Join {
+  user: string;
+  name: string;
+  room: string;
+  flow: string;
+  time: number;
+}
+
+export interface KnownRoom {
+  room: string;
+  joins: number;
+  lastJoin: number;
+}
+
+const pending = new Map<string, PendingLogin>();
+const recent: Join[] = [];
+const rooms = new Map<string, KnownRoom>();
+
+/**
+ * Removes a pending login and records its age, logins which didn't return
+ * from the identity provider count as expired.
+ */
+function finish(id: string | undefined, outcome: 'completed' | 'failed' | 'expired') {
+  const login = id ? pending.get(id) : undefined;
+
+  if (!login) {
+    return;
+  }
+
+  pending.delete(login.id);
+  loginAge.observe({provider: login.provider, outcome}, (Date.now() - login.started) / 1000);
+}
+
+function prune() {
+  const now = Date.now();
+
+  for (const [id, {started}] of pending) {
+    if (now - started > LOGIN_TIMEOUT) {
+      finish(id, 'expired');
+    }
+  }
+
+  for (const [room, {lastJoin}] of rooms) {
+    if (now - lastJoin > ROOM_RETENTION) {
+      rooms.delete(room);
+    }
+  }
+}
+
+new Gauge('jitsi_openid_logins_in_flight', 'Logins redirected to the identity provider, which did not return yet.', () => {
+  prune();
+  return pending.size;
+});
+
+/**
+ * Records a login redirected to the identity provider, the returned id has to
+ * be passed through the login flow to complete it.
+ */
+export function loginStarted(provider: string, id: string = randomBytes(8).toString('hex')): string {
+  prune();
+  pending.set(id, {id, provider, started: Date.now()});
+  loginsStarted.inc({provider});
+  log.debug(`Login started at ${provider}`, {sessionId: id});
+
+  return id;
+}
+
+export function loginCompleted(req: express.Request, provider: string, id: string | undefined) {
+  finish(id, 'completed');
+
+  loginsCompleted.inc({provider});
+  audit(req, 'login_completed', {provider});
+  log.debug(`Login completed at ${provider}`, {sessionId: id});
+}
+
+/**
+ * Records a failed login, unknown reasons are reported as other to keep the
+ * number of metric series bounded.
+ */
+export function loginFailed(req: express.Request, provider: string, id: string | undefined, error: string) {
+  const reason = FAILURE_REASONS.has(error) ? error : 'other';
+  finish(id, 'failed');
+
+  loginsFailed.inc({provider, reason});
+  audit(req, 'login_failed', {provider, reason});
+  log.warn(`Login failed at ${provider}: ${reason}`, {sessionId: id, errorKind: reason});
+}
+
+/**
+ * Records a token issued for a room, the joins are only kept in memory.
+ */
+export function recordJoin(user: string, name: string, room: string, flow: string) {
+  const time = Date.now();
+
+  recent.unshift({user, name, room, flow, time});
+  recent.splice(RECENT_JOINS);
+
+  const known = rooms.get(room);
+  rooms.set(room, {room, joins: (known?.joins ?? 0) + 1, lastJoin: time});
+}
+
+export function recentJoins(): Join[] {
+  return [...recent];
+}
+
+export function knownRooms(): KnownRoom[] {
+  prune();
+  return [...rooms.values()].sort((a, b) => b.lastJoin - a.lastJoin);
+}
+
+export function pendingLogins(): PendingLogin[] {
+  prune();
+  return [...pending.values()].sort((a, b) => b.started - a.started);
+}
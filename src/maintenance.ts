@@ -0,0 +1,27 @@
+import * as express from 'express';
+import {MAINTENANCE} from './config';
+import {errorPage} from './errors';
+
+let enabled = MAINTENANCE;
+
+export function setMaintenance(value: boolean) {
+  enabled = value;
+}
+
+export function isMaintenance(): boolean {
+  return enabled;
+}
+
+/**
+ * Answers every user facing request with 503 while in maintenance mode, using
+ * the error page so custom templates and JSON clients are covered as well.
+ */
+export function maintenance(req: express.Request, res: express.Response, next: express.NextFunction) {
+  if (!enabled) {
+    next();
+    return;
+  }
+
+  res.set('Retry-After', '600');
+  errorPage(req, res, 503, 'maintenance.message');
+}
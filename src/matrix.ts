@@ -0,0 +1,87 @@
+import * as express from 'express';
+import {BODY_LIMIT, MATRIX_SERVERS} from './config';
+import {http} from './http-client';
+import {jitsiBackend, roomUrl, sign} from './jitsi';
+import {withoutCompression} from './compression';
+import {tokenIssued} from './metrics';
+import {audit, auditToken} from './audit';
+import * as log from './log';
+import {recordJoin} from './logins';
+import {catchErrors} from './errors';
+
+interface OpenIdToken {
+  access_token?: unknown;
+  matrix_server_name?: unknown;
+}
+
+/**
+ * Resolves the federation endpoint of a homeserver using .well-known
+ * delegation, falling back to port 8448.
+ */
+async function federationUrl(serverName: string): Promise<string> {
+  try {
+    const {'m.server': server} = await http(`https://${serverName}/.well-known/matrix/server`).json<{'m.server'?: string}>();
+
+    if (server) {
+      return `https://${server}`;
+    }
+  }
+  catch (error) {
+    // no delegation
+  }
+
+  return `https://${serverName}:8448`;
+}
+
+/**
+ * Validates an OpenID token of the matrix widget api at the homeserver of the
+ * user, which returns the matrix user id.
+ */
+async function matrixUser({access_token, matrix_server_name}: OpenIdToken): Promise<string | undefined> {
+  if (typeof access_token !== 'string' || typeof matrix_server_name !== 'string' || !MATRIX_SERVERS.includes(matrix_server_name)) {
+    return undefined;
+  }
+
+  const url = await federationUrl(matrix_server_name);
+  const {sub} = await http(`${url}/_matrix/federation/v1/openid/userinfo`, {searchParams: {access_token}})
+    .json<{sub?: string}>();
+
+  return sub?.endsWith(`:${matrix_server_name}`) ? sub : undefined;
+}
+
+export const matrix = express.Router();
+
+matrix.use(express.json({limit: BODY_LIMIT}));
+
+/**
+ * Called by the jitsi widget of element with an OpenID token of the user, the
+ * conference id and the display name.
+ */
+matrix.post('/token', catchErrors(async (req, res) => {
+  const {openid_token, room, display_name} = req.body ?? {};
+
+  if (typeof openid_token !== 'object' || !openid_token || typeof room !== 'string' || !room
+    || (display_name !== undefined && typeof display_name !== 'string')) {
+    res.status(400).json({error: 'invalid_request'});
+    return;
+  }
+
+  const userId = await matrixUser(openid_token);
+
+  if (!userId) {
+    log.warn(`Rejected matrix token request from ${req.ip}: invalid openid token`, {errorKind: 'invalid_token'});
+    audit(req, 'access_denied', {flow: 'matrix', room, reason: 'invalid_token'});
+    res.status(401).json({error: 'invalid_token'});
+    return;
+  }
+
+  const backend = jitsiBackend(room, req.hostname);
+  const user = {id: userId, name: display_name ?? userId, email: ''};
+  const token = sign(user, room, backend);
+  tokenIssued('matrix', room, room);
+  auditToken(req, user, room, 'matrix', token);
+  recordJoin(userId, display_name ?? userId, room, 'matrix');
+
+  withoutCompression(res);
+  res.json({jwt: token, url: roomUrl(room, token, backend)});
+}));
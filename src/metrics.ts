@@ -0,0 +1,202 @@
+import * as express from 'express';
+import {createHash, timingSafeEqual} from 'crypto';
+import {METRICS_TOKEN, ROOM_METRICS_LIMIT} from './config';
+
+export type Labels = Record<string, string>;
+
+interface Metric {
+  render(): string[];
+}
+
+/**
+ * Receives the updates of the metrics in addition to the prometheus registry
+ * scraped at /metrics, e.g. to push them to StatsD. Gauges are read when
+ * flushed.
+ */
+export interface MetricsSink {
+  count(name: string, labels: Labels, value: number): void;
+  observe(name: string, labels: Labels, value: number): void;
+  gauge(name: string, value: number): void;
+}
+
+const registry: Metric[] = [];
+const sinks: MetricsSink[] = [];
+
+export function addSink(sink: MetricsSink) {
+  sinks.push(sink);
+}
+
+function escapeLabel(value: string): string {
+  return value.replace(/\\/g, '\\\\').replace(/\n/g, '\\n').replace(/"/g, '\\"');
+}
+
+function series(name: string, labels: Labels, value: number): string {
+  const entries = Object.entries(labels);
+
+  if (!entries.length) {
+    return `${name} ${value}`;
+  }
+
+  return `${name}{${entries.map(([key, label]) => `${key}="${escapeLabel(label)}"`).join(',')}} ${value}`;
+}
+
+export class Counter implements Metric {
+  private readonly values = new Map<string, [Labels, number]>();
+
+  constructor(private readonly name: string, private readonly help: string) {
+    registry.push(this);
+  }
+
+  inc(labels: Labels = {}, value = 1) {
+    const key = JSON.stringify(labels);
+    const current = this.values.get(key);
+
+    this.values.set(key, [labels, (current?.[1] ?? 0) + value]);
+    sinks.forEach(sink => sink.count(this.name, labels, value));
+  }
+
+  render(): string[] {
+    return [
+      `# HELP ${this.name} ${this.help}`,
+      `# TYPE ${this.name} counter`,
+      ...[...this.values.values()].map(([labels, value]) => series(this.name, labels, value)),
+    ];
+  }
+}
+
+const gauges: Gauge[] = [];
+
+/**
+ * Passes the current values of the gauges to the sinks.
+ */
+export function flushGauges() {
+  gauges.forEach(gauge => gauge.flush());
+}
+
+export class Gauge implements Metric {
+  constructor(private readonly name: string, private readonly help: string, private readonly collect: () => number) {
+    registry.push(this);
+    gauges.push(this);
+  }
+
+  flush() {
+    const value = this.collect();
+    sinks.forEach(sink => sink.gauge(this.name, value));
+  }
+
+  render(): string[] {
+    return [
+      `# HELP ${this.name} ${this.help}`,
+      `# TYPE ${this.name} gauge`,
+      series(this.name, {}, this.collect()),
+    ];
+  }
+}
+
+// the default buckets of the prometheus client libraries, in seconds
+const DEFAULT_BUCKETS = [0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1, 2.5, 5, 10];
+
+interface Observations {
+  labels: Labels;
+  buckets: number[];
+  sum: number;
+  count: number;
+}
+
+export class Histogram implements Metric {
+  private readonly values = new Map<string, Observations>();
+
+  constructor(private readonly name: string, private readonly help: string, private readonly buckets = DEFAULT_BUCKETS) {
+    registry.push(this);
+  }
+
+  observe(labels: Labels, value: number) {
+    const key = JSON.stringify(labels);
+    const observations = this.values.get(key) ?? {labels, buckets: this.buckets.map(() => 0), sum: 0, count: 0};
+
+    this.buckets.forEach((bound, index) => {
+      if (value <= bound) {
+        observations.buckets[index]++;
+      }
+    });
+
+    observations.sum += value;
+    observations.count++;
+    this.values.set(key, observations);
+    sinks.forEach(sink => sink.observe(this.name, labels, value));
+  }
+
+  render(): string[] {
+    return [
+      `# HELP ${this.name} ${this.help}`,
+      `# TYPE ${this.name} histogram`,
+      ...[...this.values.values()].flatMap(({labels, buckets, sum, count}) => [
+        ...this.buckets.map((bound, index) => series(`${this.name}_bucket`, {...labels, le: String(bound)}, buckets[index])),
+        series(`${this.name}_bucket`, {...labels, le: '+Inf'}, count),
+        series(`${this.name}_sum`, labels, sum),
+        series(`${this.name}_count`, labels, count),
+      ]),
+    ];
+  }
+}
+
+export const loginsStarted = new Counter('jitsi_openid_logins_started_total', 'Logins redirected to the identity provider.');
+export const loginsCompleted = new Counter('jitsi_openid_logins_completed_total', 'Logins completed successfully.');
+export const loginsFailed = new Counter('jitsi_openid_logins_failed_total', 'Logins failed, by reason.');
+export const tokensIssued = new Counter('jitsi_openid_tokens_issued_total', 'Jitsi tokens issued, by flow.');
+export const errors = new Counter('jitsi_openid_errors_total', 'Errors, by kind (name of the error) and variant (invalid_state, invalid_code, idp_misconfigured, ...).');
+export const idpRequestDuration = new Histogram('jitsi_openid_idp_request_duration_seconds', 'Duration of requests to identity providers, by operation and outcome.');
+// logins take seconds to minutes, they expire after 10 minutes
+const LOGIN_AGE_BUCKETS = [1, 2.5, 5, 10, 30, 60, 120, 300, 600];
+
+export const loginAge = new Histogram('jitsi_openid_login_age_seconds', 'Age of logins when they completed, failed or expired, by provider and outcome.', LOGIN_AGE_BUCKETS);
+export const roomTokensIssued = new Counter('jitsi_openid_room_tokens_issued_total', 'Jitsi tokens issued, by room and whether they are valid for all rooms.');
+
+// rooms beyond ROOM_METRICS_LIMIT share 16 series
+const ROOM_BUCKETS = 16;
+
+const rooms = new Set<string>();
+
+function digest(value: string): Buffer {
+  return createHash('sha256').update(value).digest();
+}
+
+/**
+ * The first ROOM_METRICS_LIMIT rooms keep their name (or its hash, if
+ * /metrics is public), later ones are hashed into a few buckets (other-0 to
+ * other-f), so random room names can't grow the number of series without
+ * bound.
+ */
+function roomLabel(room: string): string {
+  if (rooms.has(room) || rooms.size < ROOM_METRICS_LIMIT) {
+    rooms.add(room);
+    return METRICS_TOKEN ? room : `sha256-${digest(room).toString('hex').slice(0, 12)}`;
+  }
+
+  return `other-${(digest(room)[0] % ROOM_BUCKETS).toString(16)}`;
+}
+
+/**
+ * Counts an issued token by flow and, unless ROOM_METRICS_LIMIT is 0, by room.
+ * The allowed room is the room claim of the token, * for all rooms.
+ */
+export function tokenIssued(flow: string, room: string | undefined, allowedRoom: string) {
+  tokensIssued.inc({flow});
+
+  if (ROOM_METRICS_LIMIT) {
+    roomTokensIssued.inc({room: room ? roomLabel(room) : '', wildcard: String(allowedRoom === '*')});
+  }
+}
+
+export const metrics = express.Router();
+
+metrics.get('/metrics', (req, res) => {
+  const [scheme, token] = req.header('authorization')?.split(' ') ?? [];
+
+  if (METRICS_TOKEN && (scheme?.toLowerCase() !== 'bearer' || !token || !timingSafeEqual(digest(token), digest(METRICS_TOKEN)))) {
+    res.status(401).json({error: 'unauthorized'});
+    return;
+  }
+
+  res.type('text/plain; version=0.0.4').send(`${registry.flatMap(metric => metric.render()).join('\n')}\n`);
+});
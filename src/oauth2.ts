@@ -0,0 +1,133 @@
+import * as express from 'express';
+import {createHash, randomBytes} from 'crypto';
+import {AUTH_REDIRECT_STATUS, CALLBACK_PATH, OAuth2Provider, PKCE} from './config';
+import {getCookie, seal, transientCookie, unseal} from './cookies';
+import {joinRoom, queryString} from './join';
+import {rememberIdentity} from './sso';
+import {lacksParticipantRole} from './visitors';
+import {hostGroups} from './hosts';
+import {catchErrors, errorPage, renderIdpError} from './errors';
+import {loginCompleted, loginFailed, loginStarted} from './logins';
+import {annotate} from './log';
+import {http} from './http-client';
+
+const TRANSACTION_COOKIE = 'oauth2_transaction';
+
+interface Transaction {
+  state: string;
+  room: string;
+  query: string;
+  verifier?: string;
+}
+
+function claim(profile: Record<string, unknown>, name: string | undefined, fallback: string): string {
+  const value = profile[name ?? fallback];
+  return value === undefined || value === null ? '' : String(value);
+}
+
+/**
+ * Authorization code flow for providers which only speak OAuth2. There is no
+ * session, after the callback the user is directly redirected to the room.
+ */
+export function oauth2Router(name: string, provider: OAuth2Provider, baseURL: string): express.Router {
+  const router = express.Router();
+  const redirectUri = `${baseURL}${CALLBACK_PATH}`;
+
+  router.get('/room/:room', (req, res) => {
+    annotate({room: req.params.room});
+
+    const transaction: Transaction = {
+      state: randomBytes(16).toString('hex'),
+      room: req.params.room,
+      query: queryString(req),
+      verifier: PKCE ? randomBytes(32).toString('base64url') : undefined,
+    };
+
+    const params = new URLSearchParams();
+    params.set('response_type', 'code');
+    params.set('client_id', provider.clientID);
+    params.set('redirect_uri', redirectUri);
+    params.set('state', transaction.state);
+
+    if (transaction.verifier) {
+      params.set('code_challenge', createHash('sha256').update(transaction.verifier).digest('base64url'));
+      params.set('code_challenge_method', 'S256');
+    }
+
+    if (provider.scope) {
+      params.set('scope', provider.scope);
+    }
+
+    loginStarted(name, transaction.state);
+
+    res.cookie(TRANSACTION_COOKIE, seal(transaction), transientCookie(req));
+    res.redirect(AUTH_REDIRECT_STATUS, `${provider.authorizationURL}?${params.toString()}`);
+  });
+
+  router.get(CALLBACK_PATH, catchErrors(async (req, res) => {
+    const transaction = unseal<Transaction>(getCookie(req, TRANSACTION_COOKIE));
+    res.clearCookie(TRANSACTION_COOKIE, transientCookie(req));
+
+    const {error, error_description} = req.query;
+
+    if (typeof error === 'string') {
+      loginFailed(req, name, transaction?.state, error);
+
+      const retryUrl = transaction ? `${baseURL}/room/${encodeURIComponent(transaction.room)}` : undefined;
+      renderIdpError(req, res, error, typeof error_description === 'string' ? error_description : undefined, retryUrl);
+      return;
+    }
+
+    if (!transaction || transaction.state !== req.query.state || typeof req.query.code !== 'string') {
+      loginFailed(req, name, transaction?.state, 'invalid_state');
+      errorPage(req, res, 400, 'error.invalidState', transaction ? `${baseURL}/room/${encodeURIComponent(transaction.room)}` : undefined);
+      return;
+    }
+
+    const form: Record<string, string> = {
+      grant_type: 'authorization_code',
+      code: req.query.code,
+      redirect_uri: redirectUri,
+      client_id: provider.clientID,
+      client_secret: provider.clientSecret.reveal(),
+    };
+
+    if (transaction.verifier) {
+      form.code_verifier = transaction.verifier;
+    }
+
+    try {
+      const {access_token} = await http.post(provider.tokenURL, {
+        headers: {accept: 'application/json'},
+        form,
+      }).json<{access_token?: string}>();
+
+      if (!access_token) {
+        throw new Error('Token response is missing the access token.');
+      }
+
+      const profile = await http(provider.userinfoURL, {
+        headers: {accept: 'application/json', authorization: `Bearer ${access_token}`},
+      }).json<Record<string, unknown>>();
+
+      loginCompleted(req, name, transaction.state);
+
+      const user = {
+        id: claim(profile, provider.claims?.id, 'id'),
+        name: claim(profile, provider.claims?.name, 'name'),
+        email: claim(profile, provider.claims?.email, 'email'),
+        ...(lacksParticipantRole(profile) ? {moderator: false, visitor: true} : {}),
+        ...hostGroups(profile),
+      };
+
+      rememberIdentity(req, res, user);
+      await joinRoom(req, res, user, transaction.room, 'login', transaction.query);
+    }
+    catch (error) {
+      loginFailed(req, name, transaction.state, 'token_exchange');
+      throw error;
+    }
+  }));
+
+  return router;
+}
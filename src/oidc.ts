@@ -0,0 +1,90 @@
+import {Client, custom, Issuer} from 'openid-client';
+import {CLOCK_TOLERANCE, DEFAULT_PROVIDER, onClientSecretChange, PROVIDERS} from './config';
+import * as log from './log';
+import {idpEndpoints} from './http-client';
+
+const clients = new Map<string, Promise<Client>>();
+const discovered = new Set<string>();
+
+/**
+ * Lazily discovers the issuer of a provider and returns a client for the flows
+ * that are not handled by express-openid-connect (e.g. the device authorization
+ * grant).
+ */
+export function getClient(provider: string = DEFAULT_PROVIDER): Promise<Client> {
+  let client = clients.get(provider);
+
+  if (!client) {
+    const config = PROVIDERS[provider];
+
+    if (config.type === 'oauth2') {
+      return Promise.reject(new Error(`Provider ${provider} is not an OpenID Connect provider.`));
+    }
+
+    const {issuerBaseURL, clientID, clientSecret} = config;
+
+    client = Issuer.discover(issuerBaseURL)
+      .then(issuer => {
+        const client = new issuer.Client({
+          client_id: clientID,
+          client_secret: clientSecret?.reveal(),
+          token_endpoint_auth_method: clientSecret ? 'client_secret_basic' : 'none',
+        });
+
+        client[custom.clock_tolerance] = CLOCK_TOLERANCE;
+        discovered.add(provider);
+        idpEndpoints({
+          token: issuer.metadata.token_endpoint,
+          jwks: issuer.metadata.jwks_uri,
+          userinfo: issuer.metadata.userinfo_endpoint,
+          introspection: issuer.metadata.introspection_endpoint,
+        });
+
+        return client;
+      })
+      .catch(error => {
+        clients.delete(provider);
+        throw error;
+      });
+
+    clients.set(provider, client);
+  }
+
+  return client;
+}
+
+// the client is created again with the rotated secret on the next use
+onClientSecretChange(DEFAULT_PROVIDER, () => clients.delete(DEFAULT_PROVIDER));
+
+export function oidcProviders(): string[] {
+  return Object.entries(PROVIDERS)
+    .filter(([, provider]) => provider.type !== 'oauth2')
+    .map(([name]) => name);
+}
+
+export function isDiscovered(provider: string): boolean {
+  return discovered.has(provider);
+}
+
+const DISCOVERY_RETRY_DELAY = 5000;
+
+async function discover(provider: string): Promise<void> {
+  for (;;) {
+    try {
+      await getClient(provider);
+      return;
+    }
+    catch (error) {
+      log.error(`Discovery of provider ${provider} failed: ${error.message}`);
+      await new Promise(resolve => setTimeout(resolve, DISCOVERY_RETRY_DELAY));
+    }
+  }
+}
+
+/**
+ * Discovers all OpenID Connect providers, failed discoveries are retried
+ * until they succeed.
+ */
+export async function discoverAll(): Promise<void> {
+  await Promise.all(oidcProviders().map(discover));
+}
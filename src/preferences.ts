@@ -0,0 +1,33 @@
+import * as express from 'express';
+import {REMEMBER_PREFERENCES} from './config';
+import {getCookie, seal, transientCookie, unseal} from './cookies';
+
+const PREFERENCES_COOKIE = 'preferences';
+const PREFERENCES_MAX_AGE = 365 * 24 * 60 * 60 * 1000;
+
+/**
+ * Choices of the user kept across logins, the display name only applies to
+ * the user who chose it.
+ */
+export interface Preferences {
+  user?: string;
+  name?: string;
+  language?: string;
+}
+
+export function getPreferences(req: express.Request): Preferences {
+  return REMEMBER_PREFERENCES ? unseal<Preferences>(getCookie(req, PREFERENCES_COOKIE)) ?? {} : {};
+}
+
+export function savePreferences(req: express.Request, res: express.Response, changes: Preferences) {
+  if (!REMEMBER_PREFERENCES) {
+    return;
+  }
+
+  res.cookie(PREFERENCES_COOKIE, seal({...getPreferences(req), ...changes}), {...transientCookie(req), maxAge: PREFERENCES_MAX_AGE});
+}
+
+export function preferredName(req: express.Request, user: string): string | undefined {
+  const {user: owner, name} = getPreferences(req);
+  return owner === user ? name : undefined;
+}
@@ -0,0 +1,59 @@
+import {HTTPError} from 'got';
+import {PROSODY_MUC_DOMAIN, PROSODY_URL} from './config';
+import {http} from './http-client';
+import {jitsiBackend, sign} from './jitsi';
+
+/**
+ * Prosody validates requests to its http modules using a token signed with
+ * the secret of the deployment, just like the tokens of the participants.
+ */
+function authorization(room: string): string {
+  const token = sign({id: 'jitsi-openid', name: 'jitsi-openid', email: ''}, room, jitsiBackend(room));
+  return `Bearer ${token}`;
+}
+
+/**
+ * Ends the meeting and removes all participants, requires the muc_end_meeting
+ * module.
+ */
+export async function endMeeting(room: string) {
+  await http.post(`${PROSODY_URL}/end-meeting`, {
+    searchParams: {conference: `${room}@${PROSODY_MUC_DOMAIN}`},
+    headers: {authorization: authorization(room)},
+  });
+}
+
+/**
+ * Removes a single participant (by the participant id of jitsi) from the
+ * meeting, requires the muc_kick_participant module.
+ */
+export async function kickParticipant(room: string, participantId: string) {
+  await http.post(`${PROSODY_URL}/kick-participant`, {
+    searchParams: {room},
+    headers: {authorization: authorization(room)},
+    json: {participantId},
+  });
+}
+
+/**
+ * Number of participants in the meeting, requires the muc_size module. The
+ * module expects the domain without the muc prefix (muc.meet.jitsi ->
+ * meet.jitsi).
+ */
+export async function roomSize(room: string): Promise<number> {
+  const domain = PROSODY_MUC_DOMAIN.split('.').slice(1).join('.');
+
+  try {
+    const {participants} = await http(`${PROSODY_URL}/room-size`, {searchParams: {room, domain}})
+      .json<{participants?: number}>();
+
+    return participants ?? 0;
+  }
+  catch (error) {
+    if (error instanceof HTTPError && error.response.statusCode === 404) {
+      return 0;
+    }
+
+    throw error;
+  }
+}
@@ -0,0 +1,149 @@
+import * as net from 'net';
+import * as tls from 'tls';
+import * as log from './log';
+
+const V2_SIGNATURE = Buffer.from('0d0a0d0a000d0a515549540a', 'hex');
+const V1_PREFIX = Buffer.from('PROXY ');
+const V1_MAX_LENGTH = 107;
+const HEADER_TIMEOUT = 5000;
+
+interface Header {
+  length: number;
+  address?: string;
+}
+
+function formatIpv6(buffer: Buffer): string {
+  const groups = [];
+
+  for (let i = 0; i < 16; i += 2) {
+    groups.push(buffer.readUInt16BE(i).toString(16));
+  }
+
+  return groups.join(':');
+}
+
+function parseV2(buffer: Buffer): Header | undefined {
+  if (buffer.length < 16) {
+    return undefined;
+  }
+
+  const length = 16 + buffer.readUInt16BE(14);
+
+  if (buffer.length < length) {
+    return undefined;
+  }
+
+  const command = buffer[12] & 0x0f;
+  const family = buffer[13] >> 4;
+
+  // LOCAL connections (e.g. health checks of the proxy) keep their own address
+  if (command === 0x0) {
+    return {length};
+  }
+
+  if (family === 0x1) {
+    return {length, address: [...buffer.subarray(16, 20)].join('.')};
+  }
+
+  if (family === 0x2) {
+    return {length, address: formatIpv6(buffer.subarray(16, 32))};
+  }
+
+  return {length};
+}
+
+function parseV1(buffer: Buffer): Header | undefined {
+  const end = buffer.indexOf('\r\n');
+
+  if (end === -1) {
+    if (buffer.length >= V1_MAX_LENGTH) {
+      throw new Error('PROXY protocol v1 header is too long.');
+    }
+
+    return undefined;
+  }
+
+  const [, protocol, source] = buffer.subarray(0, end).toString('ascii').split(' ');
+
+  return {length: end + 2, address: protocol === 'TCP4' || protocol === 'TCP6' ? source : undefined};
+}
+
+function parse(buffer: Buffer): Header | undefined {
+  const prefix = buffer.subarray(0, V2_SIGNATURE.length);
+
+  if (V2_SIGNATURE.subarray(0, prefix.length).equals(prefix)) {
+    return buffer.length < V2_SIGNATURE.length ? undefined : parseV2(buffer);
+  }
+
+  if (V1_PREFIX.subarray(0, Math.min(prefix.length, V1_PREFIX.length)).equals(prefix.subarray(0, V1_PREFIX.length))) {
+    return buffer.length < V1_PREFIX.length ? undefined : parseV1(buffer);
+  }
+
+  throw new Error('Connection does not start with a PROXY protocol header.');
+}
+
+function connectionKey(socket: net.Socket): string {
+  return `${socket.remoteAddress}:${socket.remotePort}`;
+}
+
+/**
+ * Accepts connections starting with a PROXY protocol (v1 or v2) header and
+ * passes them on to the http(s) server with the client address of the header.
+ */
+export function proxyProtocolServer(target: net.Server): net.Server {
+  // a TLS socket reads the address of the wrapped connection (the load balancer), it is matched by its address and port
+  const tlsAddresses = new Map<string, string>();
+
+  if (target instanceof tls.Server) {
+    target.on('secureConnection', (tlsSocket: tls.TLSSocket) => {
+      const address = tlsAddresses.get(connectionKey(tlsSocket));
+
+      if (address) {
+        Object.defineProperty(tlsSocket, 'remoteAddress', {value: address});
+      }
+    });
+  }
+
+  return net.createServer(socket => {
+    let buffer = Buffer.alloc(0);
+
+    socket.setTimeout(HEADER_TIMEOUT, () => socket.destroy());
+
+    const onData = (chunk: Buffer) => {
+      buffer = Buffer.concat([buffer, chunk]);
+
+      let header;
+
+      try {
+        header = parse(buffer);
+      }
+      catch (error) {
+        log.warn(`Rejected connection from ${socket.remoteAddress}: ${error.message}`);
+        socket.destroy();
+        return;
+      }
+
+      if (!header) {
+        return;
+      }
+
+      socket.off('data', onData);
+      socket.setTimeout(0);
+      socket.pause();
+
+      if (header.address) {
+        const key = connectionKey(socket);
+        tlsAddresses.set(key, header.address);
+        socket.once('close', () => tlsAddresses.delete(key));
+
+        Object.defineProperty(socket, 'remoteAddress', {value: header.address});
+      }
+
+      socket.unshift(buffer.subarray(header.length));
+      target.emit('connection', socket);
+      socket.resume();
+    };
+
+    socket.on('data', onData);
+  });
+}
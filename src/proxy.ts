@@ -0,0 +1,191 @@
+import * as express from 'express';
+import * as http from 'http';
+import * as https from 'https';
+import * as net from 'net';
+import * as tls from 'tls';
+import {Duplex} from 'stream';
+import {BASE_PATH, JITSI_PROXY_UPSTREAM, SESSION_COOKIE_NAME} from './config';
+import {getCookie, transientCookie} from './cookies';
+import {errorPage} from './errors';
+import {isReserved} from './fallback';
+import {scriptJson} from './html';
+import * as log from './log';
+
+// handed from the login to the proxied room page, it is only used once
+export const TOKEN_COOKIE = 'jitsi_jwt';
+
+// headers of a single connection (RFC 7230 6.1), they must not be forwarded
+const HOP_BY_HOP = ['connection', 'keep-alive', 'proxy-authenticate', 'proxy-authorization', 'te', 'trailer', 'transfer-encoding', 'upgrade'];
+
+// cookies of this service (besides the session cookies), keep in sync with the modules setting them
+const OWN_COOKIES = [TOKEN_COOKIE, 'pending_join', 'sso', 'oauth2_transaction', 'preferences', 'last_room'];
+
+function withoutHopByHop<T extends http.IncomingHttpHeaders>(headers: T): T {
+  const connection = String(headers.connection ?? '').toLowerCase().split(',').map(name => name.trim());
+  return Object.fromEntries(Object.entries(headers)
+    .filter(([name]) => !HOP_BY_HOP.includes(name) && !connection.includes(name))) as T;
+}
+
+function isOwnCookie(name: string): boolean {
+  // session cookies are chunked (appSession.0) and suffixed with the provider (appSession_<provider>)
+  return OWN_COOKIES.includes(name)
+    || name === SESSION_COOKIE_NAME || name.startsWith(`${SESSION_COOKIE_NAME}.`) || name.startsWith(`${SESSION_COOKIE_NAME}_`)
+    || name.startsWith('auth_verification');
+}
+
+/**
+ * Removes the cookies of this service (sessions, tokens, ...), jitsi only
+ * receives its own.
+ */
+function foreignCookies(cookie: string | undefined): string | undefined {
+  const cookies = cookie?.split(';')
+    .map(entry => entry.trim())
+    .filter(entry => entry && !isOwnCookie(entry.split('=')[0].trim()));
+
+  return cookies?.length ? cookies.join('; ') : undefined;
+}
+
+function upstreamPath(url: string): string {
+  return `${new URL(JITSI_PROXY_UPSTREAM as string).pathname.replace(/\/$/, '')}${url.slice(BASE_PATH.length)}`;
+}
+
+/**
+ * Adds the token to the url before jitsi meet starts, which reads it from
+ * there and removes it from the address bar.
+ */
+function tokenScript(token: string): string {
+  return `<script>(function () {
+  var url = new URL(location.href);
+  url.searchParams.set('jwt', ${scriptJson(token)});
+  history.replaceState(history.state, '', url.toString());
+})();</script>`;
+}
+
+function forward(req: express.Request, res: express.Response, inject?: string) {
+  const target = new URL(JITSI_PROXY_UPSTREAM as string);
+
+  const {cookie, ...forwarded} = withoutHopByHop(req.headers);
+  const cookies = foreignCookies(cookie);
+  const headers: http.OutgoingHttpHeaders = {
+    ...forwarded,
+    ...(cookies ? {cookie: cookies} : {}),
+    'x-forwarded-for': req.ip,
+    'x-forwarded-proto': req.protocol,
+    'x-forwarded-host': req.get('host'),
+    ...(inject ? {'accept-encoding': 'identity'} : {}),
+  };
+
+  const request = (target.protocol === 'https:' ? https : http).request({
+    hostname: target.hostname,
+    port: target.port,
+    method: req.method,
+    path: upstreamPath(req.originalUrl),
+    headers,
+  }, response => {
+    const status = response.statusCode ?? 502;
+
+    if (!inject || !response.headers['content-type']?.startsWith('text/html')) {
+      res.writeHead(status, withoutHopByHop(response.headers));
+      response.pipe(res);
+      return;
+    }
+
+    const chunks: Buffer[] = [];
+    response.on('data', chunk => chunks.push(chunk));
+    response.on('end', () => {
+      // the page contains the token now, it must neither be cached (or revalidated) like the upstream page nor compressed
+      const {
+        'content-length': length, 'cache-control': cacheControl, expires, etag, 'last-modified': lastModified, ...rest
+      } = withoutHopByHop(response.headers);
+      const body = Buffer.concat(chunks).toString().replace(/<head[^>]*>/i, head => `${head}${inject}`);
+
+      res.status(status).set(rest as Record<string, string>).set({'Cache-Control': 'no-store, no-transform', Pragma: 'no-cache'}).send(body);
+    });
+  });
+
+  request.on('error', error => {
+    log.error(`Proxying ${req.method} ${req.path} to jitsi failed: ${error.message}`, {errorKind: 'proxy'});
+
+    if (res.headersSent) {
+      res.destroy();
+      return;
+    }
+
+    errorPage(req, res, 502, 'error.jitsiUnreachable', req.originalUrl);
+  });
+
+  req.pipe(request);
+}
+
+/**
+ * Serves the jitsi web ui from JITSI_PROXY_UPSTREAM. Room pages require a
+ * token of the login flow, which is injected into the page instead of being
+ * part of the meeting url. Paths of this service are never proxied.
+ */
+export function jitsiProxy(req: express.Request, res: express.Response, next: express.NextFunction) {
+  const segment = req.path.split('/')[1];
+
+  if (segment && isReserved(segment)) {
+    next();
+    return;
+  }
+
+  const room = req.method === 'GET' && req.accepts('html') ? req.path.match(/^\/([^/.]+)\/?$/)?.[1] : undefined;
+
+  if (!room) {
+    forward(req, res);
+    return;
+  }
+
+  const token = getCookie(req, TOKEN_COOKIE);
+
+  if (!token) {
+    const query = req.originalUrl.indexOf('?');
+    res.redirect(`${BASE_PATH}/room/${room}${query === -1 ? '' : req.originalUrl.slice(query)}`);
+    return;
+  }
+
+  res.clearCookie(TOKEN_COOKIE, transientCookie(req));
+  forward(req, res, tokenScript(token));
+}
+
+/**
+ * Proxies websocket connections (xmpp-websocket, colibri-ws) to jitsi.
+ */
+export function proxyUpgrades(server: http.Server) {
+  server.on('upgrade', (req: http.IncomingMessage, socket: Duplex, head: Buffer) => {
+    const target = new URL(JITSI_PROXY_UPSTREAM as string);
+    const port = Number(target.port || (target.protocol === 'https:' ? 443 : 80));
+
+    const connection = target.protocol === 'https:'
+      ? tls.connect({host: target.hostname, port, servername: target.hostname})
+      : net.connect(port, target.hostname);
+
+    const headers = [];
+
+    // connection and upgrade are kept, they are part of the websocket handshake
+    for (let i = 0; i < req.rawHeaders.length; i += 2) {
+      if (req.rawHeaders[i].toLowerCase() !== 'cookie') {
+        headers.push(`${req.rawHeaders[i]}: ${req.rawHeaders[i + 1]}`);
+      }
+    }
+
+    const cookies = foreignCookies(req.headers.cookie);
+
+    if (cookies) {
+      headers.push(`Cookie: ${cookies}`);
+    }
+
+    connection.write(`${req.method} ${upstreamPath(req.url ?? '/')} HTTP/1.1\r\n${headers.join('\r\n')}\r\n\r\n`);
+    connection.write(head);
+
+    connection.pipe(socket).pipe(connection);
+
+    connection.on('error', error => {
+      log.error(`Proxying websocket ${req.url} to jitsi failed: ${error.message}`, {errorKind: 'proxy'});
+      socket.destroy();
+    });
+
+    socket.on('error', () => connection.destroy());
+  });
+}
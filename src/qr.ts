@@ -0,0 +1,349 @@
+/**
+ * Minimal QR code encoder (byte mode, error correction level M, versions 1 to
+ * 10), enough for links to rooms.
+ */
+
+// per version: error correction codewords per block and the data codewords of each block
+const BLOCKS: [number, number[]][] = [
+  [10, [16]],
+  [16, [28]],
+  [26, [44]],
+  [18, [32, 32]],
+  [24, [43, 43]],
+  [16, [27, 27, 27, 27]],
+  [18, [31, 31, 31, 31]],
+  [22, [38, 38, 39, 39]],
+  [22, [36, 36, 36, 37, 37]],
+  [26, [43, 43, 43, 43, 44]],
+];
+
+const ALIGNMENT_POSITIONS: number[][] = [
+  [], [6, 18], [6, 22], [6, 26], [6, 30], [6, 34], [6, 22, 38], [6, 24, 42], [6, 26, 46], [6, 28, 50],
+];
+
+const MASKS: ((x: number, y: number) => boolean)[] = [
+  (x, y) => (x + y) % 2 === 0,
+  (x, y) => y % 2 === 0,
+  (x) => x % 3 === 0,
+  (x, y) => (x + y) % 3 === 0,
+  (x, y) => (Math.floor(x / 3) + Math.floor(y / 2)) % 2 === 0,
+  (x, y) => x * y % 2 + x * y % 3 === 0,
+  (x, y) => (x * y % 2 + x * y % 3) % 2 === 0,
+  (x, y) => ((x + y) % 2 + x * y % 3) % 2 === 0,
+];
+
+function multiply(x: number, y: number): number {
+  let z = 0;
+
+  for (let i = 7; i >= 0; i--) {
+    z = (z << 1) ^ ((z >>> 7) * 0x11d);
+    z ^= ((y >>> i) & 1) * x;
+  }
+
+  return z;
+}
+
+function errorCorrection(data: number[], degree: number): number[] {
+  const divisor = new Array<number>(degree).fill(0);
+  divisor[degree - 1] = 1;
+  let root = 1;
+
+  for (let i = 0; i < degree; i++) {
+    for (let j = 0; j < degree; j++) {
+      divisor[j] = multiply(divisor[j], root);
+
+      if (j + 1 < degree) {
+        divisor[j] ^= divisor[j + 1];
+      }
+    }
+
+    root = multiply(root, 0x02);
+  }
+
+  const result = new Array<number>(degree).fill(0);
+
+  for (const byte of data) {
+    const factor = byte ^ (result.shift() as number);
+    result.push(0);
+    divisor.forEach((coefficient, index) => result[index] ^= multiply(coefficient, factor));
+  }
+
+  return result;
+}
+
+function codewords(data: Buffer, version: number): number[] {
+  const [ecLength, blockLengths] = BLOCKS[version - 1];
+  const capacity = blockLengths.reduce((sum, length) => sum + length, 0);
+  const bits: number[] = [];
+
+  const append = (value: number, length: number) => {
+    for (let i = length - 1; i >= 0; i--) {
+      bits.push((value >>> i) & 1);
+    }
+  };
+
+  append(0b0100, 4);
+  append(data.length, version < 10 ? 8 : 16);
+  data.forEach(byte => append(byte, 8));
+  append(0, Math.min(4, capacity * 8 - bits.length));
+  append(0, (8 - bits.length % 8) % 8);
+
+  const bytes: number[] = [];
+
+  for (let i = 0; i < bits.length; i += 8) {
+    bytes.push(parseInt(bits.slice(i, i + 8).join(''), 2));
+  }
+
+  for (let pad = 0xec; bytes.length < capacity; pad ^= 0xec ^ 0x11) {
+    bytes.push(pad);
+  }
+
+  const blocks: number[][] = [];
+  let offset = 0;
+
+  for (const length of blockLengths) {
+    blocks.push(bytes.slice(offset, offset + length));
+    offset += length;
+  }
+
+  const ecBlocks = blocks.map(block => errorCorrection(block, ecLength));
+  const result: number[] = [];
+
+  for (let i = 0; i < Math.max(...blockLengths); i++) {
+    blocks.filter(block => i < block.length).forEach(block => result.push(block[i]));
+  }
+
+  for (let i = 0; i < ecLength; i++) {
+    ecBlocks.forEach(block => result.push(block[i]));
+  }
+
+  return result;
+}
+
+class Matrix {
+  readonly modules: boolean[][];
+  readonly reserved: boolean[][];
+
+  constructor(readonly size: number) {
+    this.modules = Array.from({length: size}, () => new Array<boolean>(size).fill(false));
+    this.reserved = Array.from({length: size}, () => new Array<boolean>(size).fill(false));
+  }
+
+  set(x: number, y: number, dark: boolean) {
+    this.modules[y][x] = dark;
+    this.reserved[y][x] = true;
+  }
+}
+
+function drawFunctionPatterns(matrix: Matrix, version: number) {
+  const {size} = matrix;
+
+  for (let i = 0; i < size; i++) {
+    matrix.set(6, i, i % 2 === 0);
+    matrix.set(i, 6, i % 2 === 0);
+  }
+
+  for (const [cx, cy] of [[3, 3], [size - 4, 3], [3, size - 4]]) {
+    for (let dy = -4; dy <= 4; dy++) {
+      for (let dx = -4; dx <= 4; dx++) {
+        const distance = Math.max(Math.abs(dx), Math.abs(dy));
+        const x = cx + dx;
+        const y = cy + dy;
+
+        if (x >= 0 && x < size && y >= 0 && y < size) {
+          matrix.set(x, y, distance !== 2 && distance !== 4);
+        }
+      }
+    }
+  }
+
+  const positions = ALIGNMENT_POSITIONS[version - 1];
+  const last = positions.length - 1;
+
+  positions.forEach((cx, i) => positions.forEach((cy, j) => {
+    if ((i === 0 && j === 0) || (i === 0 && j === last) || (i === last && j === 0)) {
+      return;
+    }
+
+    for (let dy = -2; dy <= 2; dy++) {
+      for (let dx = -2; dx <= 2; dx++) {
+        matrix.set(cx + dx, cy + dy, Math.max(Math.abs(dx), Math.abs(dy)) !== 1);
+      }
+    }
+  }));
+
+  // reserves the format areas, they are drawn once the mask is known
+  drawFormat(matrix, 0);
+
+  if (version >= 7) {
+    let remainder = version;
+
+    for (let i = 0; i < 12; i++) {
+      remainder = (remainder << 1) ^ ((remainder >>> 11) * 0x1f25);
+    }
+
+    const bits = version << 12 | remainder;
+
+    for (let i = 0; i < 18; i++) {
+      const dark = ((bits >>> i) & 1) === 1;
+      const a = size - 11 + i % 3;
+      const b = Math.floor(i / 3);
+      matrix.set(a, b, dark);
+      matrix.set(b, a, dark);
+    }
+  }
+}
+
+// error correction level M is encoded as 00
+function drawFormat(matrix: Matrix, mask: number) {
+  const {size} = matrix;
+  let remainder = mask;
+
+  for (let i = 0; i < 10; i++) {
+    remainder = (remainder << 1) ^ ((remainder >>> 9) * 0x537);
+  }
+
+  const bits = (mask << 10 | remainder) ^ 0x5412;
+  const bit = (i: number) => ((bits >>> i) & 1) === 1;
+
+  for (let i = 0; i <= 5; i++) {
+    matrix.set(8, i, bit(i));
+  }
+
+  matrix.set(8, 7, bit(6));
+  matrix.set(8, 8, bit(7));
+  matrix.set(7, 8, bit(8));
+
+  for (let i = 9; i < 15; i++) {
+    matrix.set(14 - i, 8, bit(i));
+  }
+
+  for (let i = 0; i < 8; i++) {
+    matrix.set(size - 1 - i, 8, bit(i));
+  }
+
+  for (let i = 8; i < 15; i++) {
+    matrix.set(8, size - 15 + i, bit(i));
+  }
+
+  matrix.set(8, size - 8, true);
+}
+
+function drawData(matrix: Matrix, data: number[]) {
+  const {size} = matrix;
+  let i = 0;
+
+  for (let right = size - 1; right >= 1; right -= 2) {
+    if (right === 6) {
+      right = 5;
+    }
+
+    for (let vertical = 0; vertical < size; vertical++) {
+      for (let j = 0; j < 2; j++) {
+        const x = right - j;
+        const upward = ((right + 1) & 2) === 0;
+        const y = upward ? size - 1 - vertical : vertical;
+
+        if (!matrix.reserved[y][x] && i < data.length * 8) {
+          matrix.modules[y][x] = ((data[i >>> 3] >>> (7 - (i & 7))) & 1) === 1;
+          i++;
+        }
+      }
+    }
+  }
+}
+
+function applyMask(matrix: Matrix, mask: number) {
+  for (let y = 0; y < matrix.size; y++) {
+    for (let x = 0; x < matrix.size; x++) {
+      if (!matrix.reserved[y][x] && MASKS[mask](x, y)) {
+        matrix.modules[y][x] = !matrix.modules[y][x];
+      }
+    }
+  }
+}
+
+// simplified penalty (runs, 2x2 blocks and balance), any mask results in a valid code
+function penalty({modules, size}: Matrix): number {
+  let score = 0;
+  let dark = 0;
+
+  for (let a = 0; a < size; a++) {
+    let rowRun = 1;
+    let columnRun = 1;
+
+    for (let b = 1; b < size; b++) {
+      rowRun = modules[a][b] === modules[a][b - 1] ? rowRun + 1 : 1;
+      columnRun = modules[b][a] === modules[b - 1][a] ? columnRun + 1 : 1;
+      score += (rowRun === 5 ? 3 : rowRun > 5 ? 1 : 0) + (columnRun === 5 ? 3 : columnRun > 5 ? 1 : 0);
+    }
+  }
+
+  for (let y = 0; y < size; y++) {
+    for (let x = 0; x < size; x++) {
+      dark += modules[y][x] ? 1 : 0;
+
+      if (x > 0 && y > 0 && modules[y][x] === modules[y - 1][x] && modules[y][x] === modules[y][x - 1] && modules[y][x] === modules[y - 1][x - 1]) {
+        score += 3;
+      }
+    }
+  }
+
+  return score + Math.floor(Math.abs(dark * 20 - size * size * 10) / (size * size)) * 10;
+}
+
+// the smallest version fitting the data, 0 if it is too long
+function fittingVersion(data: Buffer): number {
+  // mode and length take 2 bytes (3 bytes from version 10)
+  return BLOCKS.findIndex(([, lengths], index) => lengths.reduce((sum, length) => sum + length, 0) - (index < 9 ? 2 : 3) >= data.length) + 1;
+}
+
+/**
+ * Whether the text fits into a QR code of version 10 (213 bytes).
+ */
+export function fitsQr(text: string): boolean {
+  return fittingVersion(Buffer.from(text, 'utf8')) !== 0;
+}
+
+export function encodeQr(text: string): boolean[][] {
+  const data = Buffer.from(text, 'utf8');
+  const version = fittingVersion(data);
+
+  if (!version) {
+    throw new Error('Text is too long for a QR code.');
+  }
+
+  const words = codewords(data, version);
+  let best: Matrix | undefined;
+  let bestPenalty = Infinity;
+
+  for (let mask = 0; mask < MASKS.length; mask++) {
+    const matrix = new Matrix(17 + version * 4);
+    drawFunctionPatterns(matrix, version);
+    drawData(matrix, words);
+    applyMask(matrix, mask);
+    drawFormat(matrix, mask);
+
+    const score = penalty(matrix);
+
+    if (score < bestPenalty) {
+      best = matrix;
+      bestPenalty = score;
+    }
+  }
+
+  return (best as Matrix).modules;
+}
+
+/**
+ * Renders the QR code as scalable SVG, including the quiet zone.
+ */
+export function qrSvg(text: string): string {
+  const modules = encodeQr(text);
+  const size = modules.length + 8;
+  const path = modules
+    .flatMap((row, y) => row.map((dark, x) => dark ? `M${x + 4},${y + 4}h1v1h-1z` : ''))
+    .join('');
+
+  return `<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 ${size} ${size}" shape-rendering="crispEdges"><rect width="${size}" height="${size}" fill="#fff"/><path d="${path}" fill="#000"/></svg>`;
+}
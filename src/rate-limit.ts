@@ -0,0 +1,60 @@
+import * as express from 'express';
+import {CALLBACK_PATH, RATE_LIMIT_BURST, RATE_LIMIT_RATE} from './config';
+import {Counter} from './metrics';
+import {errorPage} from './errors';
+
+interface Bucket {
+  tokens: number;
+  updated: number;
+}
+
+function isLimited(path: string): boolean {
+  return /(^|\/)room\//.test(path) || path.endsWith(CALLBACK_PATH);
+}
+
+const buckets = new Map<string, Bucket>();
+const rateLimited = new Counter('jitsi_openid_rate_limited_total', 'Requests rejected by the rate limit.');
+
+function refill(bucket: Bucket, now: number) {
+  bucket.tokens = Math.min(RATE_LIMIT_BURST, bucket.tokens + (now - bucket.updated) / 1000 * RATE_LIMIT_RATE);
+  bucket.updated = now;
+}
+
+// full buckets don't carry any information and are dropped
+setInterval(() => {
+  const now = Date.now();
+
+  for (const [ip, bucket] of buckets) {
+    refill(bucket, now);
+
+    if (bucket.tokens >= RATE_LIMIT_BURST) {
+      buckets.delete(ip);
+    }
+  }
+}, 60 * 1000).unref();
+
+/**
+ * Token bucket per client ip for the login flow, so a single client can't
+ * exhaust the identity provider.
+ */
+export function rateLimit(req: express.Request, res: express.Response, next: express.NextFunction) {
+  if (!RATE_LIMIT_RATE || !isLimited(req.path)) {
+    next();
+    return;
+  }
+
+  const now = Date.now();
+  const bucket = buckets.get(req.ip) ?? {tokens: RATE_LIMIT_BURST, updated: now};
+  refill(bucket, now);
+  buckets.set(req.ip, bucket);
+
+  if (bucket.tokens < 1) {
+    rateLimited.inc();
+    res.set('Retry-After', String(Math.ceil((1 - bucket.tokens) / RATE_LIMIT_RATE)));
+    errorPage(req, res, 429, 'error.rateLimited');
+    return;
+  }
+
+  bucket.tokens -= 1;
+  next();
+}
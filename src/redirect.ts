@@ -0,0 +1,15 @@
+import * as http from 'http';
+import {BASE_URL} from './config';
+
+/**
+ * Plain http server which only redirects permanently to the https origin of
+ * the base url.
+ */
+export function createRedirectServer(): http.Server {
+  const origin = new URL(BASE_URL).origin;
+
+  return http.createServer((req, res) => {
+    res.writeHead(308, {'Location': `${origin}${req.url ?? '/'}`, 'Connection': 'close'});
+    res.end();
+  });
+}
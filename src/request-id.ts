@@ -0,0 +1,26 @@
+import * as express from 'express';
+import {randomBytes} from 'crypto';
+import {withRequestId} from './log';
+
+const HEADER = 'X-Request-Id';
+const VALID_ID = /^[\w.\-]{1,128}$/;
+
+declare global {
+  namespace Express {
+    interface Request {
+      id: string;
+    }
+  }
+}
+
+/**
+ * Propagates the request id of an upstream proxy (or generates one) and
+ * returns it to the client, so users can quote it to operators.
+ */
+export function requestId(req: express.Request, res: express.Response, next: express.NextFunction) {
+  const upstream = req.header(HEADER);
+  req.id = upstream && VALID_ID.test(upstream) ? upstream : randomBytes(8).toString('hex');
+
+  res.set(HEADER, req.id);
+  withRequestId(req.id, next);
+}
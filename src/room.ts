@@ -0,0 +1,239 @@
+import * as express from 'express';
+import {auth as openIdAuth} from 'express-openid-connect';
+import {
+  AUTH_MODE,
+  AUTH_REDIRECT_STATUS,
+  BASE_URL,
+  CALLBACK_PATH,
+  CLOCK_TOLERANCE,
+  DASHBOARD_ROLE,
+  DIAL_IN_NUMBERS,
+  MULTIPLE_PROVIDERS,
+  OidcProvider,
+  PKCE,
+  PROVIDERS,
+  SESSION_COOKIE_DOMAIN,
+  SESSION_COOKIE_MAX_AGE,
+  SESSION_COOKIE_NAME,
+  SESSION_COOKIE_PATH,
+  SESSION_COOKIE_SAME_SITE,
+  SESSION_COOKIE_SECURE,
+  SMTP_URL,
+  SSO_COOKIE_MAX_AGE,
+  onClientSecretChange,
+} from './config';
+import {escapeHtml, page} from './html';
+import {translator} from './i18n';
+import {oauth2Router} from './oauth2';
+import {introspection} from './introspection';
+import {directory} from './directory';
+import {dashboard} from './dashboard';
+import {dialIn} from './dialin';
+import {mailInvite} from './invite';
+import {catchErrors, idpErrors, rememberRoom} from './errors';
+import {loginCompleted, loginStarted} from './logins';
+import {joinFlow, joinRoom, queryString, roomPolicy} from './join';
+import {forgetIdentity, rememberIdentity, singleSignOn} from './sso';
+import {guests, hasPasscode} from './guest';
+import {lacksParticipantRole} from './visitors';
+import {hostGroups} from './hosts';
+
+function join(req: express.Request, res: express.Response, next: express.NextFunction) {
+  if (!req.oidc.user) {
+    throw new Error('Missing user information.');
+  }
+
+  const {sub, name, email} = req.oidc.user;
+  const user = {
+    id: sub,
+    name,
+    email,
+    ...(lacksParticipantRole(req.oidc.user) ? {moderator: false, visitor: true} : {}),
+    ...hostGroups(req.oidc.user),
+  };
+
+  rememberIdentity(req, res, user);
+  // the query string is kept by express-openid-connect, which returns to the original url
+  joinRoom(req, res, user, req.params.room, 'login', queryString(req)).catch(next);
+}
+
+/**
+ * express-openid-connect redirects to the identity provider using
+ * res.redirect(url), which is answered with AUTH_REDIRECT_STATUS instead of
+ * 302. Redirects with an explicit status (e.g. to jitsi) are kept.
+ */
+function authRedirects(req: express.Request, res: express.Response, next: express.NextFunction) {
+  const redirect = res.redirect.bind(res) as (status: number, url: string) => void;
+
+  res.redirect = ((...args: [string] | [number, string]) => {
+    if (args.length === 1) {
+      redirect(AUTH_REDIRECT_STATUS, args[0]);
+      return;
+    }
+
+    redirect(...args);
+  }) as express.Response['redirect'];
+
+  next();
+}
+
+/**
+ * express-openid-connect always adds a code challenge to the code flow, with
+ * PKCE=false it is removed from the redirect to the identity provider. The
+ * verifier is still sent to the token endpoint, providers without PKCE
+ * support ignore it as unknown parameter.
+ */
+function withoutCodeChallenge(req: express.Request, res: express.Response, next: express.NextFunction) {
+  const redirect = res.redirect.bind(res) as (...args: [string] | [number, string]) => void;
+
+  res.redirect = ((...args: [string] | [number, string]) => {
+    const url = args.length === 1 ? args[0] : args[1];
+
+    if (!/[?&]code_challenge=/.test(url)) {
+      redirect(...args);
+      return;
+    }
+
+    const location = new URL(url);
+    location.searchParams.delete('code_challenge');
+    location.searchParams.delete('code_challenge_method');
+
+    redirect(...(args.length === 1 ? [location.toString()] as [string] : [args[0], location.toString()] as [number, string]));
+  }) as express.Response['redirect'];
+
+  next();
+}
+
+function openIdRouter(name: string, provider: OidcProvider, baseURL: string): express.RequestHandler {
+  const {issuerBaseURL, clientID, clientSecret} = provider;
+
+  return openIdAuth({
+    issuerBaseURL,
+    clientID,
+    clientSecret: clientSecret?.reveal(),
+    baseURL,
+    clockTolerance: CLOCK_TOLERANCE,
+    routes: {callback: CALLBACK_PATH},
+    getLoginState: (req, options) => ({
+      returnTo: options.returnTo ?? req.originalUrl,
+      loginId: loginStarted(name),
+    }),
+    afterCallback: (req, res, session, state) => {
+      loginCompleted(req, name, state.loginId);
+      return session;
+    },
+    // express-openid-connect requires a client secret for the code flow
+    authorizationParams: clientSecret
+      ? {response_type: 'code', scope: 'openid profile email'}
+      : {response_type: 'id_token', response_mode: 'form_post', scope: 'openid profile email'},
+    session: {
+      name: MULTIPLE_PROVIDERS ? `${SESSION_COOKIE_NAME}_${name}` : SESSION_COOKIE_NAME,
+      ...(SESSION_COOKIE_MAX_AGE ? {rolling: false, absoluteDuration: SESSION_COOKIE_MAX_AGE} : {}),
+      cookie: {
+        domain: SESSION_COOKIE_DOMAIN,
+        path: SESSION_COOKIE_PATH,
+        sameSite: SESSION_COOKIE_SAME_SITE,
+        secure: SESSION_COOKIE_SECURE,
+      },
+    },
+  });
+}
+
+function providerRouter(name: string): express.Router {
+  const provider = PROVIDERS[name];
+  const baseURL = MULTIPLE_PROVIDERS ? `${BASE_URL}/${name}` : BASE_URL;
+
+  if (provider.type === 'oauth2') {
+    return oauth2Router(name, provider, baseURL);
+  }
+
+  const router = express.Router();
+
+  router.get('/room/:room', rememberRoom);
+
+  if (AUTH_REDIRECT_STATUS !== 302) {
+    router.use(authRedirects);
+  }
+
+  if (!PKCE && provider.clientSecret) {
+    router.use(withoutCodeChallenge);
+  }
+
+  // rebuilt with the new client secret when it is rotated (e.g. in vault)
+  let authenticate = openIdRouter(name, provider, baseURL);
+  onClientSecretChange(name, () => {
+    authenticate = openIdRouter(name, provider, baseURL);
+  });
+
+  router.use((req, res, next) => authenticate(req, res, next));
+
+  router.get('/room/:room', join);
+  router.get('/rooms', directory(baseURL));
+
+  if (DIAL_IN_NUMBERS.length) {
+    router.get('/room/:room/dialin', catchErrors(dialIn));
+  }
+
+  if (SMTP_URL) {
+    router.use(mailInvite);
+  }
+
+  if (DASHBOARD_ROLE) {
+    router.get('/dashboard', dashboard(baseURL));
+  }
+
+  router.use(idpErrors(name, baseURL));
+
+  return router;
+}
+
+function chooser(req: express.Request, res: express.Response) {
+  const room = encodeURIComponent(req.params.room);
+  const query = queryString(req);
+
+  const links = Object.entries(PROVIDERS)
+    .map(([name, {label}]) => `<li><a href="${escapeHtml(`${BASE_URL}/${name}/room/${room}${query ? `?${query}` : ''}`)}">${escapeHtml(label ?? name)}</a></li>`)
+    .join('');
+
+  const {t, language} = translator(req);
+  const guest = hasPasscode(req.params.room)
+    ? `<li><a href="${escapeHtml(`${BASE_URL}/room/${room}/guest${query ? `?${query}` : ''}`)}">${escapeHtml(t('chooser.guest'))}</a></li>`
+    : '';
+
+  res.send(page(t('chooser.title'), `<p>${escapeHtml(t('chooser.intro'))}</p><ul>${links}${guest}</ul>`, language));
+}
+
+/**
+ * Mounts the login flow of every configured provider. A single provider is
+ * mounted at the root, multiple providers are mounted below their name and
+ * /room/:room shows a chooser page. In introspection mode no login flow is
+ * mounted at all.
+ */
+export function rooms(app: express.Router) {
+  const roomPaths = MULTIPLE_PROVIDERS ? ['/room/:room', '/:provider/room/:room'] : '/room/:room';
+
+  app.use(roomPaths, roomPolicy);
+  app.use(joinFlow);
+  app.use(guests);
+
+  if (AUTH_MODE === 'introspection') {
+    app.use(introspection);
+    return;
+  }
+
+  if (SSO_COOKIE_MAX_AGE) {
+    app.get(roomPaths, singleSignOn);
+    app.get(MULTIPLE_PROVIDERS ? ['/logout', '/:provider/logout'] : '/logout', forgetIdentity);
+  }
+
+  if (!MULTIPLE_PROVIDERS) {
+    app.use(providerRouter(Object.keys(PROVIDERS)[0]));
+    return;
+  }
+
+  app.get('/room/:room', chooser);
+
+  for (const name of Object.keys(PROVIDERS)) {
+    app.use(`/${name}`, providerRouter(name));
+  }
+}
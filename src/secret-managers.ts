@@ -0,0 +1,158 @@
+import got from 'got';
+import {createHash, createHmac} from 'crypto';
+import {addResolvedReference, pendingFileValues} from './config-file';
+
+// secrets which may reference a cloud secret manager, e.g. JITSI_SECRET=aws-sm://jitsi-openid#jitsi_secret
+export const SECRET_VARIABLES = ['JITSI_SECRET', 'CLIENT_SECRET', 'SECRET', 'ADMIN_TOKEN'];
+
+const REFERENCE = /^(aws-sm|gcp-sm):\/\/([^#]+)(?:#(.+))?$/;
+
+export function isSecretReference(value: string | undefined): boolean {
+  return !!value && REFERENCE.test(value);
+}
+
+const GCP_TOKEN_URL = 'http://metadata.google.internal/computeMetadata/v1/instance/service-accounts/default/token';
+
+function sha256(value: string): string {
+  return createHash('sha256').update(value).digest('hex');
+}
+
+function hmac(key: string | Buffer, value: string): Buffer {
+  return createHmac('sha256', key).update(value).digest();
+}
+
+/**
+ * Reads a secret from AWS Secrets Manager, signing the request (SigV4) with the
+ * credentials of the environment.
+ */
+async function awsSecret(secretId: string): Promise<string> {
+  const {AWS_ACCESS_KEY_ID, AWS_SECRET_ACCESS_KEY, AWS_SESSION_TOKEN} = process.env;
+  const region = process.env.AWS_REGION ?? process.env.AWS_DEFAULT_REGION;
+
+  if (!AWS_ACCESS_KEY_ID || !AWS_SECRET_ACCESS_KEY || !region) {
+    throw new Error('AWS_ACCESS_KEY_ID, AWS_SECRET_ACCESS_KEY and AWS_REGION are required.');
+  }
+
+  const host = `secretsmanager.${region}.amazonaws.com`;
+  const body = JSON.stringify({SecretId: secretId});
+  const amzDate = new Date().toISOString().replace(/[:-]|\.\d{3}/g, '');
+  const date = amzDate.slice(0, 8);
+
+  const headers: Record<string, string> = {
+    'content-type': 'application/x-amz-json-1.1',
+    'host': host,
+    'x-amz-date': amzDate,
+    'x-amz-target': 'secretsmanager.GetSecretValue',
+    ...(AWS_SESSION_TOKEN ? {'x-amz-security-token': AWS_SESSION_TOKEN} : {}),
+  };
+
+  const signedHeaders = Object.keys(headers).sort().join(';');
+  const canonicalHeaders = Object.keys(headers).sort().map(name => `${name}:${headers[name]}\n`).join('');
+  const canonicalRequest = ['POST', '/', '', canonicalHeaders, signedHeaders, sha256(body)].join('\n');
+  const scope = `${date}/${region}/secretsmanager/aws4_request`;
+  const stringToSign = ['AWS4-HMAC-SHA256', amzDate, scope, sha256(canonicalRequest)].join('\n');
+
+  const signingKey = ['secretsmanager', 'aws4_request']
+    .reduce((key, value) => hmac(key, value), hmac(hmac(`AWS4${AWS_SECRET_ACCESS_KEY}`, date), region));
+  const signature = hmac(signingKey, stringToSign).toString('hex');
+
+  const {SecretString} = await got.post(`https://${host}/`, {
+    headers: {
+      ...headers,
+      authorization: `AWS4-HMAC-SHA256 Credential=${AWS_ACCESS_KEY_ID}/${scope}, SignedHeaders=${signedHeaders}, Signature=${signature}`,
+    },
+    body,
+  }).json<{SecretString?: string}>();
+
+  if (SecretString === undefined) {
+    throw new Error(`Secret ${secretId} has no string value.`);
+  }
+
+  return SecretString;
+}
+
+/**
+ * Reads a secret version from GCP Secret Manager using the service account of
+ * the metadata server.
+ */
+async function gcpSecret(name: string): Promise<string> {
+  const {access_token} = await got(GCP_TOKEN_URL, {headers: {'metadata-flavor': 'Google'}})
+    .json<{access_token: string}>();
+
+  const {payload} = await got(`https://secretmanager.googleapis.com/v1/${name}:access`, {
+    headers: {authorization: `Bearer ${access_token}`},
+  }).json<{payload: {data: string}}>();
+
+  return Buffer.from(payload.data, 'base64').toString();
+}
+
+async function resolve(reference: string): Promise<string | undefined> {
+  const match = reference.match(REFERENCE);
+
+  if (!match) {
+    return undefined;
+  }
+
+  const [, provider, id, field] = match;
+  const value = provider === 'aws-sm' ? await awsSecret(id) : await gcpSecret(id);
+
+  if (!field) {
+    return value;
+  }
+
+  let fields: Record<string, unknown>;
+
+  try {
+    fields = JSON.parse(value);
+  }
+  catch (error) {
+    throw new Error(`Secret ${id} is not a JSON object, which is required to read the field ${field}: ${error.message}`);
+  }
+
+  const fieldValue = fields?.[field];
+
+  if (typeof fieldValue !== 'string') {
+    throw new Error(`Secret ${id} has no field ${field}.`);
+  }
+
+  return fieldValue;
+}
+
+/**
+ * Replaces references to AWS Secrets Manager (aws-sm://secret-id#field) and
+ * GCP Secret Manager (gcp-sm://projects/p/secrets/s/versions/latest#field) in
+ * the environment with the referenced values, before the config is loaded.
+ * References in the config file (or NAME_FILE) are resolved as well and
+ * replaced when the file is loaded.
+ */
+export async function resolveSecretReferences() {
+  for (const name of SECRET_VARIABLES) {
+    const reference = process.env[name];
+
+    if (!reference) {
+      continue;
+    }
+
+    try {
+      const value = await resolve(reference);
+
+      if (value !== undefined) {
+        process.env[name] = value;
+      }
+    }
+    catch (error) {
+      console.error(`Unable to resolve ${name} from ${reference.split('#')[0]}: ${error.message}`);
+      process.exit(1);
+    }
+  }
+
+  for (const reference of pendingFileValues(SECRET_VARIABLES).filter(isSecretReference)) {
+    try {
+      addResolvedReference(reference, await resolve(reference) as string);
+    }
+    catch (error) {
+      console.error(`Unable to resolve ${reference.split('#')[0]} of the config file: ${error.message}`);
+      process.exit(1);
+    }
+  }
+}
@@ -0,0 +1,41 @@
+import {inspect} from 'util';
+
+const REDACTED = '[redacted]';
+
+/**
+ * Holds a secret in a buffer, which is hidden from logs, inspection and
+ * serialization and wiped once the secret is replaced (e.g. on reload).
+ * Strings passed to libraries (reveal) can't be wiped, they are left to the
+ * garbage collector.
+ */
+export class Secret {
+  private readonly value: Buffer;
+
+  constructor(value: string) {
+    this.value = Buffer.from(value, 'utf8');
+  }
+
+  bytes(): Buffer {
+    return this.value;
+  }
+
+  reveal(): string {
+    return this.value.toString('utf8');
+  }
+
+  wipe() {
+    this.value.fill(0);
+  }
+
+  toString(): string {
+    return REDACTED;
+  }
+
+  toJSON(): string {
+    return REDACTED;
+  }
+
+  [inspect.custom](): string {
+    return `Secret(${REDACTED})`;
+  }
+}
@@ -0,0 +1,24 @@
+import * as express from 'express';
+import {BASE_URL, SECURITY_HEADERS} from './config';
+
+const DEFAULT_HEADERS: Record<string, string> = {
+  'X-Content-Type-Options': 'nosniff',
+  // redirects carry the jwt in the query string, it must not leak to other sites
+  'Referrer-Policy': 'no-referrer',
+  'Cache-Control': 'no-store',
+  ...(BASE_URL.startsWith('https:') ? {'Strict-Transport-Security': 'max-age=31536000'} : {}),
+};
+
+/**
+ * Headers configured using SECURITY_HEADERS replace the defaults, an empty
+ * value removes a header.
+ */
+export function securityHeaders(req: express.Request, res: express.Response, next: express.NextFunction) {
+  for (const [name, value] of Object.entries({...DEFAULT_HEADERS, ...SECURITY_HEADERS})) {
+    if (value !== '') {
+      res.set(name, value);
+    }
+  }
+
+  next();
+}
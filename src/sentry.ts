@@ -0,0 +1,95 @@
+import * as express from 'express';
+import got from 'got';
+import {randomBytes} from 'crypto';
+import {hostname} from 'os';
+import {SENTRY_DSN, SENTRY_ENVIRONMENT} from './config';
+import {currentRequestId, redact} from './log';
+import * as log from './log';
+import {VERSION} from './version';
+
+interface Frame {
+  function?: string;
+  filename: string;
+  lineno: number;
+  colno: number;
+}
+
+/**
+ * Parses the V8 stack trace, sentry expects the frames from the oldest to the
+ * newest call.
+ */
+function frames(stack: string | undefined): Frame[] {
+  return (stack ?? '')
+    .split('\n')
+    .map(line => line.match(/^\s*at (?:(.+?) \()?(.+?):(\d+):(\d+)\)?$/))
+    .filter((match): match is RegExpMatchArray => !!match)
+    .map(([, fn, filename, lineno, colno]) => ({function: fn, filename, lineno: Number(lineno), colno: Number(colno)}))
+    .reverse();
+}
+
+/**
+ * Sends the error to sentry (or a compatible service like GlitchTip), the
+ * query string is omitted, because it may contain authorization codes.
+ */
+export async function reportError(error: Error, req?: express.Request): Promise<void> {
+  if (!SENTRY_DSN) {
+    return;
+  }
+
+  const dsn = new URL(SENTRY_DSN);
+  const project = dsn.pathname.split('/').pop();
+  const path = dsn.pathname.slice(0, dsn.pathname.lastIndexOf('/'));
+  const requestId = req?.id ?? currentRequestId();
+
+  try {
+    await got.post(`${dsn.protocol}//${dsn.host}${path}/api/${project}/store/`, {
+      headers: {
+        'x-sentry-auth': `Sentry sentry_version=7, sentry_client=jitsi-openid/${VERSION.version}, sentry_key=${dsn.username}`,
+      },
+      json: {
+        event_id: randomBytes(16).toString('hex'),
+        timestamp: new Date().toISOString(),
+        platform: 'node',
+        level: 'error',
+        server_name: hostname(),
+        release: VERSION.version,
+        environment: SENTRY_ENVIRONMENT,
+        tags: {request_id: requestId},
+        exception: {
+          values: [{type: error.name, value: redact(error.message), stacktrace: {frames: frames(error.stack)}}],
+        },
+        request: req ? {
+          method: req.method,
+          url: `${req.protocol}://${req.get('host')}${req.originalUrl.split('?')[0]}`,
+          headers: {'User-Agent': req.get('user-agent')},
+        } : undefined,
+      },
+      timeout: 5000,
+    });
+  }
+  catch (requestError) {
+    log.warn(`Reporting the error to sentry failed: ${requestError.message}`);
+  }
+}
+
+/**
+ * Logs unhandled rejections instead of terminating the process (the default
+ * of node), which would abort the requests of all users. Uncaught exceptions
+ * still terminate it, after they were reported.
+ */
+export function reportCrashes() {
+  process.on('unhandledRejection', reason => {
+    const error = reason instanceof Error ? reason : new Error(String(reason));
+    log.error(`Unhandled rejection: ${error.stack ?? error.message}`, {errorKind: error.name});
+    reportError(error);
+  });
+
+  if (!SENTRY_DSN) {
+    return;
+  }
+
+  process.on('uncaughtException', error => {
+    log.error(`Uncaught exception: ${error.stack ?? error.message}`, {errorKind: error.name});
+    reportError(error).finally(() => process.exit(1));
+  });
+}
@@ -0,0 +1,212 @@
+import * as express from 'express';
+import * as http from 'http';
+import * as https from 'https';
+import {readFileSync} from 'fs';
+import {
+  BASE_PATH,
+  HEADERS_TIMEOUT,
+  HTTP_REDIRECT_ADDR,
+  JITSI_PROXY_UPSTREAM,
+  KEEP_ALIVE_TIMEOUT,
+  LOG_FORMAT,
+  LOG_LEVEL,
+  LISTEN_ADDR,
+  MATRIX_SERVERS,
+  MAX_CONNECTIONS,
+  MAX_HEADER_SIZE,
+  PROXY_PROTOCOL,
+  REQUEST_TIMEOUT,
+  ROOM_FALLBACK,
+  ROUTE_ALIASES,
+  SHUTDOWN_GRACE_PERIOD,
+  SLACK_SIGNING_SECRET,
+  SLASH_COMMAND_TOKENS,
+  SYSLOG_ADDR,
+  SYSLOG_APP_NAME,
+  SYSLOG_FACILITY,
+  TLS_CERT,
+  TLS_KEY,
+  TRUSTED_PROXIES,
+  configSummary,
+  reloadConfig,
+} from './config';
+import {device} from './device';
+import {api} from './api';
+import {slashCommand} from './slash-command';
+import {matrix} from './matrix';
+import {rooms} from './room';
+import {health, startHealthChecks} from './health';
+import {discoverAll} from './oidc';
+import {errors, metrics} from './metrics';
+import {exportToStatsd} from './statsd';
+import {formatAddress, listen, parseAddresses, systemdAddresses} from './listen';
+import {gracefulShutdown, rejectWhileDraining} from './shutdown';
+import {notifyReady} from './systemd';
+import {requestId} from './request-id';
+import {trace} from './trace';
+import {accessLog} from './access-log';
+import {tracing} from './tracing';
+import {securityHeaders} from './security';
+import {cors} from './cors';
+import {rateLimit} from './rate-limit';
+import {version, VERSION} from './version';
+import {compression} from './compression';
+import {proxyProtocolServer} from './proxy-protocol';
+import {instrumentRuntime} from './instrumentation';
+import {staticFiles} from './static';
+import {landing} from './landing';
+import {shortLinks} from './links';
+import {invite} from './invite';
+import {roomFallback, routeAliases} from './fallback';
+import {jitsiProxy, proxyUpgrades} from './proxy';
+import {maintenance} from './maintenance';
+import {admin} from './admin';
+import {createRedirectServer} from './redirect';
+import * as log from './log';
+import {configureLogging} from './log';
+import {reportCrashes, reportError} from './sentry';
+import {syslogSink} from './syslog';
+import {errorId, errorPage, errorVariant} from './errors';
+
+function createApp(): express.Express {
+  const app = express();
+
+  if (TRUSTED_PROXIES) {
+    app.set('trust proxy', TRUSTED_PROXIES.split(',').map(proxy => proxy.trim()));
+  }
+
+  app.use(requestId);
+  app.use(tracing);
+  app.use(trace);
+  app.use(accessLog);
+  app.use(securityHeaders);
+  app.use(compression);
+
+  const router = express.Router();
+
+  router.use(health);
+  router.use(metrics);
+  router.use(version);
+  router.use(staticFiles);
+  router.use('/admin', admin);
+  router.use(maintenance);
+  router.use(landing);
+  router.use(shortLinks);
+  router.use(invite);
+  router.use(rejectWhileDraining);
+  router.use(rateLimit);
+  router.use('/device', cors, device);
+  router.use('/api', cors, api);
+
+  if (MATRIX_SERVERS.length) {
+    router.use('/matrix', cors, matrix);
+  }
+
+  if (SLACK_SIGNING_SECRET || SLASH_COMMAND_TOKENS.length) {
+    router.use('/slash-command', slashCommand);
+  }
+
+  if (Object.keys(ROUTE_ALIASES).length) {
+    router.use(routeAliases);
+  }
+
+  if (JITSI_PROXY_UPSTREAM) {
+    router.use(jitsiProxy);
+  }
+  else if (ROOM_FALLBACK) {
+    router.use(roomFallback);
+  }
+
+  rooms(router);
+
+  app.use(BASE_PATH || '/', router);
+  app.use((req, res) => errorPage(req, res, 404, 'error.notFound'));
+
+  app.use((err: Error & {status?: number}, req: express.Request, res: express.Response, next: express.NextFunction) => {
+    const variant = errorVariant(err);
+    errors.inc({kind: err.name, variant});
+    errorId(res);
+    log.error(`${req.method} ${req.originalUrl} from ${req.ip} failed: ${err.stack ?? err.message}`, {errorKind: variant});
+
+    if ((err.status ?? 500) >= 500) {
+      reportError(err, req);
+    }
+
+    if (res.headersSent) {
+      next(err);
+      return;
+    }
+
+    errorPage(req, res, err.status ?? 500);
+  });
+
+  return app;
+}
+
+function createServer(app: express.Express): http.Server {
+  const server = TLS_CERT && TLS_KEY
+    ? https.createServer({cert: readFileSync(TLS_CERT), key: readFileSync(TLS_KEY), maxHeaderSize: MAX_HEADER_SIZE}, app)
+    : http.createServer({maxHeaderSize: MAX_HEADER_SIZE}, app);
+
+  server.requestTimeout = REQUEST_TIMEOUT * 1000;
+  server.headersTimeout = HEADERS_TIMEOUT * 1000;
+  server.keepAliveTimeout = KEEP_ALIVE_TIMEOUT * 1000;
+  server.maxConnections = MAX_CONNECTIONS;
+
+  return server;
+}
+
+/**
+ * Starts listening on all configured addresses and reports the readiness
+ * after the discovery completed.
+ */
+export function serve() {
+  configureLogging(LOG_FORMAT, LOG_LEVEL, SYSLOG_ADDR ? syslogSink(SYSLOG_ADDR, SYSLOG_FACILITY, SYSLOG_APP_NAME) : undefined);
+  log.info(`Starting jitsi-openid ${VERSION.version}.`, configSummary());
+  reportCrashes();
+  instrumentRuntime();
+  exportToStatsd();
+
+  const app = createApp();
+  const addresses = systemdAddresses() ?? parseAddresses(LISTEN_ADDR);
+
+  const servers = addresses.map(address => {
+    const httpServer = createServer(app);
+
+    if (JITSI_PROXY_UPSTREAM) {
+      proxyUpgrades(httpServer);
+    }
+
+    const server = PROXY_PROTOCOL ? proxyProtocolServer(httpServer) : httpServer;
+    server.maxConnections = MAX_CONNECTIONS;
+    listen(server, address, addresses, () => log.info(`${TLS_CERT ? 'Https' : 'Http'} Server is listening on ${formatAddress(address)}.`));
+    return server;
+  });
+
+  if (HTTP_REDIRECT_ADDR) {
+    const redirectAddresses = parseAddresses(HTTP_REDIRECT_ADDR);
+
+    for (const address of redirectAddresses) {
+      const server = createRedirectServer();
+      listen(server, address, redirectAddresses, () => log.info(`Http redirect Server is listening on ${formatAddress(address)}.`));
+      servers.push(server);
+    }
+  }
+
+  gracefulShutdown(servers, SHUTDOWN_GRACE_PERIOD);
+
+  process.on('SIGHUP', () => {
+    const problems = reloadConfig();
+
+    if (problems.length) {
+      log.error(`Configuration was not reloaded: ${problems.join(' ')}`);
+      return;
+    }
+
+    configureLogging(LOG_FORMAT, LOG_LEVEL);
+    log.info('Configuration reloaded.');
+  });
+
+  discoverAll().then(notifyReady);
+  startHealthChecks();
+}
@@ -0,0 +1,48 @@
+import * as express from 'express';
+import * as net from 'net';
+import * as log from './log';
+import {errorPage} from './errors';
+
+let draining = false;
+
+export function isDraining(): boolean {
+  return draining;
+}
+
+/**
+ * Rejects new logins while draining, callbacks of logins already in progress
+ * are still served.
+ */
+export function rejectWhileDraining(req: express.Request, res: express.Response, next: express.NextFunction) {
+  if (draining && /(^|\/)room\//.test(req.path)) {
+    res.set('Connection', 'close').set('Retry-After', '10');
+    errorPage(req, res, 503, 'error.shuttingDown', req.originalUrl);
+    return;
+  }
+
+  next();
+}
+
+/**
+ * On SIGTERM/SIGINT stops accepting new logins, waits the grace period for
+ * in-flight logins to return from the identity provider and exits afterwards.
+ */
+export function gracefulShutdown(servers: net.Server[], gracePeriod: number) {
+  const shutdown = (signal: string) => {
+    if (draining) {
+      return;
+    }
+
+    draining = true;
+    log.info(`Received ${signal}, draining for ${gracePeriod} seconds.`);
+
+    setTimeout(() => {
+      servers.forEach(server => server.close());
+      log.info('Shutdown complete.');
+      process.exit(0);
+    }, gracePeriod * 1000).unref();
+  };
+
+  process.on('SIGTERM', shutdown);
+  process.on('SIGINT', shutdown);
+}
@@ -0,0 +1,70 @@
+import * as express from 'express';
+import {createHmac, randomBytes, timingSafeEqual} from 'crypto';
+import {BASE_URL, BODY_LIMIT, SLACK_SIGNING_SECRET, SLASH_COMMAND_TOKENS} from './config';
+import * as log from './log';
+
+// requests older than this are rejected to prevent replays (slack recommends five minutes)
+const MAX_AGE = 5 * 60;
+
+const rawBodies = new WeakMap<express.Request, Buffer>();
+
+function equal(a: string, b: string): boolean {
+  return a.length === b.length && timingSafeEqual(Buffer.from(a), Buffer.from(b));
+}
+
+function isSlack(req: express.Request): boolean {
+  return req.header('x-slack-signature') !== undefined;
+}
+
+/**
+ * Slack signs the raw body using the signing secret, mattermost sends one of
+ * the configured command tokens as form field.
+ */
+function verify(req: express.Request): boolean {
+  if (isSlack(req)) {
+    const timestamp = req.header('x-slack-request-timestamp') ?? '';
+
+    if (!SLACK_SIGNING_SECRET || Math.abs(Date.now() / 1000 - Number(timestamp)) > MAX_AGE) {
+      return false;
+    }
+
+    const expected = `v0=${createHmac('sha256', SLACK_SIGNING_SECRET).update(`v0:${timestamp}:${rawBodies.get(req) ?? ''}`).digest('hex')}`;
+    return equal(expected, req.header('x-slack-signature') ?? '');
+  }
+
+  const token = typeof req.body?.token === 'string' ? req.body.token : '';
+  return !!token && SLASH_COMMAND_TOKENS.some(allowed => equal(allowed, token));
+}
+
+function roomName(text: string): string {
+  const room = text.trim().toLowerCase().replace(/\s+/g, '-');
+  return room || `meet-${randomBytes(4).toString('hex')}`;
+}
+
+export const slashCommand = express.Router();
+
+slashCommand.use(express.urlencoded({
+  extended: false,
+  limit: BODY_LIMIT,
+  verify: (req, res, buffer) => rawBodies.set(req as express.Request, buffer),
+}));
+
+/**
+ * Slash command (e.g. /meet standup) for slack and mattermost, responding
+ * with the link to the room, which starts the login.
+ */
+slashCommand.post('/', (req, res) => {
+  if (!verify(req)) {
+    log.warn(`Rejected slash command from ${req.ip}: invalid token or signature`, {errorKind: 'invalid_token'});
+    res.status(401).json({text: 'Invalid token.'});
+    return;
+  }
+
+  const room = roomName(typeof req.body.text === 'string' ? req.body.text : '');
+  const url = `${BASE_URL}/room/${encodeURIComponent(room)}`;
+
+  res.json({
+    response_type: 'in_channel',
+    text: isSlack(req) ? `Join the meeting *${room}*: <${url}|${url}>` : `Join the meeting **${room}**: [${url}](${url})`,
+  });
+});
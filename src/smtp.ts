@@ -0,0 +1,199 @@
+import {randomUUID} from 'crypto';
+import {createConnection, Socket} from 'net';
+import {hostname} from 'os';
+import {connect as tlsConnect} from 'tls';
+import {SMTP_ALLOW_PLAINTEXT, SMTP_FROM, SMTP_URL} from './config';
+
+const TIMEOUT = 30 * 1000;
+
+export interface Mail {
+  to: string[];
+  subject: string;
+  text: string;
+  replyTo?: string;
+}
+
+interface Reply {
+  code: number;
+  text: string;
+}
+
+/**
+ * Reads the replies of the smtp server, replies arriving before they are
+ * awaited are queued.
+ */
+class Session {
+  private socket!: Socket;
+  private buffer = '';
+  private lines: string[] = [];
+  private replies: Reply[] = [];
+  private waiting: Array<{resolve: (reply: Reply) => void, reject: (error: Error) => void}> = [];
+  private error?: Error;
+
+  use(socket: Socket) {
+    this.socket = socket;
+    socket.setEncoding('utf8');
+    socket.setTimeout(TIMEOUT, () => socket.destroy(new Error('SMTP server timed out.')));
+    socket.on('data', (chunk: string) => this.receive(chunk));
+    socket.on('error', error => this.fail(error));
+    socket.on('close', () => this.fail(new Error('SMTP server closed the connection.')));
+  }
+
+  detach(): Socket {
+    this.socket.removeAllListeners('data');
+    this.socket.removeAllListeners('error');
+    this.socket.removeAllListeners('close');
+    this.socket.setTimeout(0);
+    return this.socket;
+  }
+
+  end() {
+    this.detach().end();
+  }
+
+  private receive(chunk: string) {
+    this.buffer += chunk;
+    let index: number;
+
+    while ((index = this.buffer.indexOf('\r\n')) !== -1) {
+      const line = this.buffer.slice(0, index);
+      this.buffer = this.buffer.slice(index + 2);
+      this.lines.push(line.slice(4));
+
+      // all but the last line of a reply have a dash after the code (RFC 5321 4.2.1)
+      if (line[3] !== '-') {
+        const reply = {code: Number(line.slice(0, 3)), text: this.lines.join('\n')};
+        this.lines = [];
+
+        const waiting = this.waiting.shift();
+        waiting ? waiting.resolve(reply) : this.replies.push(reply);
+      }
+    }
+  }
+
+  private fail(error: Error) {
+    this.error = this.error ?? error;
+    this.waiting.splice(0).forEach(({reject}) => reject(error));
+  }
+
+  private reply(): Promise<Reply> {
+    const reply = this.replies.shift();
+
+    if (reply) {
+      return Promise.resolve(reply);
+    }
+
+    if (this.error) {
+      return Promise.reject(this.error);
+    }
+
+    return new Promise((resolve, reject) => this.waiting.push({resolve, reject}));
+  }
+
+  /**
+   * Sends a command (or only awaits the greeting) and expects a reply of the
+   * given class (2 for 2xx, 3 for 3xx).
+   */
+  async command(line: string | undefined, expected: number, name = line?.split(' ')[0] ?? 'the connection'): Promise<Reply> {
+    if (line !== undefined) {
+      this.socket.write(`${line}\r\n`);
+    }
+
+    const reply = await this.reply();
+
+    if (Math.floor(reply.code / 100) !== expected) {
+      throw new Error(`SMTP server rejected ${name}: ${reply.code} ${reply.text}`);
+    }
+
+    return reply;
+  }
+}
+
+function connected(socket: Socket, event: string): Promise<Socket> {
+  return new Promise((resolve, reject) => {
+    socket.once(event, () => {
+      socket.removeListener('error', reject);
+      resolve(socket);
+    });
+    socket.once('error', reject);
+  });
+}
+
+// encoded words (RFC 2047) for headers with other characters than ascii
+function header(value: string): string {
+  return /^[\x20-\x7e]*$/.test(value) ? value : `=?UTF-8?B?${Buffer.from(value).toString('base64')}?=`;
+}
+
+export function address(mailbox: string): string {
+  return /<([^>]+)>/.exec(mailbox)?.[1] ?? mailbox.trim();
+}
+
+function message({to, subject, text, replyTo}: Mail, from: string): string {
+  // base64 keeps the lines short and needs no dot stuffing
+  const body = Buffer.from(text.replace(/\r?\n/g, '\r\n')).toString('base64').replace(/.{76}/g, '$&\r\n');
+
+  return [
+    `From: ${from}`,
+    `To: ${to.join(', ')}`,
+    ...(replyTo ? [`Reply-To: ${replyTo}`] : []),
+    `Subject: ${header(subject)}`,
+    `Date: ${new Date().toUTCString()}`,
+    `Message-ID: <${randomUUID()}@${hostname()}>`,
+    'MIME-Version: 1.0',
+    'Content-Type: text/plain; charset=utf-8',
+    'Content-Transfer-Encoding: base64',
+    '',
+    body,
+  ].join('\r\n');
+}
+
+/**
+ * Delivers a mail to SMTP_URL, smtps:// uses implicit tls, smtp:// upgrades
+ * the connection with STARTTLS. Servers not offering it (or a man in the
+ * middle removing it) are refused unless SMTP_ALLOW_PLAINTEXT is set, before
+ * the credentials of the url are sent using AUTH PLAIN.
+ */
+export async function sendMail(mail: Mail) {
+  const url = new URL(SMTP_URL as string);
+  const host = url.hostname.replace(/^\[(.*)]$/, '$1');
+  const implicitTls = url.protocol === 'smtps:';
+  const port = Number(url.port || (implicitTls ? 465 : 587));
+  const from = SMTP_FROM as string;
+
+  const session = new Session();
+  session.use(implicitTls
+    ? await connected(tlsConnect({host, port, servername: host}), 'secureConnect')
+    : await connected(createConnection(port, host), 'connect'));
+
+  try {
+    await session.command(undefined, 2);
+    const {text: extensions} = await session.command(`EHLO ${hostname()}`, 2);
+
+    if (!implicitTls && /^STARTTLS$/im.test(extensions)) {
+      await session.command('STARTTLS', 2);
+      session.use(await connected(tlsConnect({socket: session.detach(), servername: host}), 'secureConnect'));
+      await session.command(`EHLO ${hostname()}`, 2);
+    }
+    else if (!implicitTls && !SMTP_ALLOW_PLAINTEXT) {
+      throw new Error(`${host} doesn't offer STARTTLS, set SMTP_ALLOW_PLAINTEXT for relays without tls.`);
+    }
+
+    if (url.username) {
+      const credentials = `\0${decodeURIComponent(url.username)}\0${decodeURIComponent(url.password)}`;
+      await session.command(`AUTH PLAIN ${Buffer.from(credentials).toString('base64')}`, 2);
+    }
+
+    await session.command(`MAIL FROM:<${address(from)}>`, 2);
+
+    for (const recipient of mail.to) {
+      await session.command(`RCPT TO:<${recipient}>`, 2);
+    }
+
+    await session.command('DATA', 3);
+    await session.command(`${message(mail, from)}\r\n.`, 2, 'the message');
+    await session.command('QUIT', 2);
+  }
+  finally {
+    session.end();
+  }
+}
@@ -0,0 +1,42 @@
+import * as express from 'express';
+import {SSO_COOKIE_MAX_AGE} from './config';
+import {getCookie, seal, transientCookie, unseal} from './cookies';
+import {JitsiUser} from './jitsi';
+import {joinRoom, queryString} from './join';
+
+const SSO_COOKIE = 'sso';
+
+interface Identity {
+  user: JitsiUser;
+  expires: number;
+}
+
+/**
+ * Keeps the identity of a completed login in a sealed cookie, so joining
+ * further rooms within SSO_COOKIE_MAX_AGE skips the identity provider.
+ */
+export function rememberIdentity(req: express.Request, res: express.Response, user: JitsiUser) {
+  if (!SSO_COOKIE_MAX_AGE) {
+    return;
+  }
+
+  const maxAge = SSO_COOKIE_MAX_AGE * 1000;
+  res.cookie(SSO_COOKIE, seal({user, expires: Date.now() + maxAge}), {...transientCookie(req), maxAge});
+}
+
+export function singleSignOn(req: express.Request, res: express.Response, next: express.NextFunction) {
+  const identity = unseal<Identity>(getCookie(req, SSO_COOKIE));
+
+  // the expiry is checked as well, the lifetime of the cookie is up to the browser
+  if (!identity || identity.expires <= Date.now()) {
+    next();
+    return;
+  }
+
+  joinRoom(req, res, identity.user, req.params.room, 'sso', queryString(req)).catch(next);
+}
+
+export function forgetIdentity(req: express.Request, res: express.Response, next: express.NextFunction) {
+  res.clearCookie(SSO_COOKIE, transientCookie(req));
+  next();
+}
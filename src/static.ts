@@ -0,0 +1,18 @@
+import * as express from 'express';
+import {existsSync} from 'fs';
+import {join} from 'path';
+import {BASE_PATH, STATIC_DIR} from './config';
+
+export const staticFiles = express.Router();
+
+if (STATIC_DIR) {
+  staticFiles.use('/static', express.static(STATIC_DIR, {index: false, maxAge: '1h'}));
+}
+
+function staticUrl(file: string): string | undefined {
+  return STATIC_DIR && existsSync(join(STATIC_DIR, file)) ? `${BASE_PATH}/static/${file}` : undefined;
+}
+
+// branding picked up by all generated pages
+export const STYLESHEET_URL = staticUrl('style.css');
+export const LOGO_URL = staticUrl('logo.svg') ?? staticUrl('logo.png');
@@ -0,0 +1,49 @@
+import {createSocket} from 'dgram';
+import {STATSD_ADDR, STATSD_PREFIX} from './config';
+import {addSink, flushGauges, Labels} from './metrics';
+
+const FLUSH_INTERVAL = 10 * 1000;
+
+// tags in the DogStatsD format, understood by datadog, telegraf and the statsd exporter of prometheus
+function tags(labels: Labels): string {
+  const entries = Object.entries(labels);
+  return entries.length ? `|#${entries.map(([key, value]) => `${key}:${value.replace(/[|,#:\n]/g, '_')}`).join(',')}` : '';
+}
+
+// counters are sent without the _total suffix of prometheus
+function metricName(name: string): string {
+  return `${STATSD_PREFIX}${name.replace(/_total$/, '')}`;
+}
+
+/**
+ * Pushes the metrics to StatsD at udp://host:port, for environments where
+ * scraping short lived pods isn't feasible. Durations (metrics ending with
+ * _seconds) are sent as timers in milliseconds, gauges every 10 seconds.
+ */
+export function exportToStatsd() {
+  if (!STATSD_ADDR) {
+    return;
+  }
+
+  const url = new URL(STATSD_ADDR);
+  const host = url.hostname.replace(/^\[(.*)]$/, '$1');
+  const port = Number(url.port || 8125);
+  const socket = createSocket(host.includes(':') ? 'udp6' : 'udp4');
+  socket.unref();
+
+  const send = (line: string) => socket.send(line, port, host, error => {
+    if (error) {
+      process.stderr.write(`Sending metrics to statsd failed: ${error.message}\n`);
+    }
+  });
+
+  addSink({
+    count: (name, labels, value) => send(`${metricName(name)}:${value}|c${tags(labels)}`),
+    observe: (name, labels, value) => send(name.endsWith('_seconds')
+      ? `${metricName(name).replace(/_seconds$/, '_ms')}:${(value * 1000).toFixed(3)}|ms${tags(labels)}`
+      : `${metricName(name)}:${value}|h${tags(labels)}`),
+    gauge: (name, value) => send(`${metricName(name)}:${value}|g`),
+  });
+
+  setInterval(flushGauges, FLUSH_INTERVAL).unref();
+}
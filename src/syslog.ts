@@ -0,0 +1,66 @@
+import {createSocket} from 'dgram';
+import {createConnection, Socket} from 'net';
+import {hostname} from 'os';
+import {Level, Sink} from './log';
+
+const FACILITIES: Record<string, number> = {
+  user: 1, daemon: 3, auth: 4, local0: 16, local1: 17, local2: 18, local3: 19, local4: 20, local5: 21, local6: 22, local7: 23,
+};
+
+const SEVERITIES: Record<Level, number> = {debug: 7, info: 6, warn: 4, error: 3};
+
+export function isFacility(value: string): boolean {
+  return value in FACILITIES;
+}
+
+/**
+ * Formats a RFC 5424 message, the log line (text or json) is used as message
+ * without structured data.
+ */
+function format(facility: number, appName: string, level: Level, line: string): string {
+  const priority = facility * 8 + SEVERITIES[level];
+  return `<${priority}>1 ${new Date().toISOString()} ${hostname()} ${appName} ${process.pid} - - ${line}`;
+}
+
+function tcpSink(host: string, port: number, path: string | undefined): (message: string) => void {
+  let socket: Socket | undefined;
+
+  return message => {
+    if (!socket || socket.destroyed) {
+      socket = path ? createConnection(path) : createConnection(port, host);
+      socket.on('error', error => {
+        process.stderr.write(`Syslog connection failed: ${error.message}\n`);
+        socket?.destroy();
+      });
+    }
+
+    // octet counting framing (RFC 6587)
+    socket.write(`${Buffer.byteLength(message)} ${message}`);
+  };
+}
+
+function udpSink(host: string, port: number): (message: string) => void {
+  const socket = createSocket(host.includes(':') ? 'udp6' : 'udp4');
+  socket.unref();
+
+  return message => socket.send(message, port, host, error => {
+    if (error) {
+      process.stderr.write(`Sending to syslog failed: ${error.message}\n`);
+    }
+  });
+}
+
+/**
+ * Creates a sink sending log lines to syslog at udp://host:port,
+ * tcp://host:port or unix:/path (stream socket).
+ */
+export function syslogSink(address: string, facility: string, appName: string): Sink {
+  const url = new URL(address);
+  const host = url.hostname.replace(/^\[(.*)]$/, '$1');
+
+  const send = url.protocol === 'udp:'
+    ? udpSink(host, Number(url.port || 514))
+    : tcpSink(host, Number(url.port || 601), url.protocol === 'unix:' ? url.pathname : undefined);
+
+  return (level, line) => send(format(FACILITIES[facility], appName, level, line));
+}
@@ -0,0 +1,19 @@
+import {execFile} from 'child_process';
+import * as log from './log';
+
+/**
+ * Notifies systemd (Type=notify) about the readiness of the service. Node is
+ * not able to write to the unix datagram NOTIFY_SOCKET itself, so systemd-notify
+ * is used, which requires NotifyAccess=all.
+ */
+export function notifyReady() {
+  if (!process.env.NOTIFY_SOCKET) {
+    return;
+  }
+
+  execFile('systemd-notify', ['--ready', `--pid=${process.pid}`], error => {
+    if (error) {
+      log.error(`Unable to notify systemd: ${error.message}`);
+    }
+  });
+}
@@ -0,0 +1,80 @@
+import * as express from 'express';
+import {AsyncLocalStorage} from 'async_hooks';
+import {HTTP_LOG, SLOW_REQUEST_THRESHOLD} from './config';
+import {locate} from './geoip';
+import * as log from './log';
+
+// operations of the identity providers, as recorded by the http client
+const IDP_OPERATIONS = ['discovery', 'token', 'jwks', 'userinfo', 'introspection'];
+
+const timings = new AsyncLocalStorage<Record<string, number>>();
+
+/**
+ * Adds the duration of an outgoing request (by operation, e.g. token) to the
+ * timings of the current request.
+ */
+export function recordTiming(operation: string, durationMs: number) {
+  const current = timings.getStore();
+
+  if (current) {
+    current[operation] = (current[operation] ?? 0) + durationMs;
+  }
+}
+
+/**
+ * Warns about requests involving the identity provider (callbacks, token
+ * exchanges, ...) taking longer than SLOW_REQUEST_THRESHOLD, with the time
+ * spent per operation and in this service.
+ */
+function logSlowRequest(req: express.Request, path: string, duration: number, spent: Record<string, number>) {
+  if (!SLOW_REQUEST_THRESHOLD || duration < SLOW_REQUEST_THRESHOLD || !IDP_OPERATIONS.some(operation => operation in spent)) {
+    return;
+  }
+
+  const upstream = Object.values(spent).reduce((sum, value) => sum + value, 0);
+  const breakdown = {...spent, app: Math.max(duration - upstream, 0)};
+
+  log.warn(`Slow request ${req.method} ${path} took ${duration.toFixed(1)}ms (${Object.entries(breakdown).map(([operation, value]) => `${operation} ${value.toFixed(1)}ms`).join(', ')})`, {
+    method: req.method,
+    path,
+    durationMs: Math.round(duration),
+    ...Object.fromEntries(Object.entries(breakdown).map(([operation, value]) => [`${operation}Ms`, Math.round(value)])),
+  });
+}
+
+/**
+ * Logs method, path, status and latency of every request (HTTP_LOG=all) or
+ * only of failed requests (HTTP_LOG=errors). The query string is omitted, it
+ * may contain authorization codes.
+ */
+export function trace(req: express.Request, res: express.Response, next: express.NextFunction) {
+  const start = process.hrtime.bigint();
+  const spent: Record<string, number> = {};
+
+  res.on('finish', () => {
+    const duration = Number(process.hrtime.bigint() - start) / 1e6;
+    const path = req.originalUrl.split('?')[0];
+
+    logSlowRequest(req, path, duration, spent);
+
+    if (HTTP_LOG === 'off' || (HTTP_LOG === 'errors' && res.statusCode < 400)) {
+      return;
+    }
+
+    const message = `${req.method} ${path} ${res.statusCode} ${duration.toFixed(1)}ms ${req.ip}`;
+    const {country, asn} = locate(req.ip);
+    const fields = {method: req.method, path, status: res.statusCode, durationMs: Math.round(duration), clientIp: req.ip, country, asn, errorId: res.locals.errorId};
+
+    if (res.statusCode >= 500) {
+      log.error(message, fields);
+    }
+    else if (res.statusCode >= 400) {
+      log.warn(message, fields);
+    }
+    else {
+      log.info(message, fields);
+    }
+  });
+
+  timings.run(spent, next);
+}
@@ -0,0 +1,177 @@
+import * as express from 'express';
+import {AsyncLocalStorage} from 'async_hooks';
+import {randomBytes} from 'crypto';
+import got, {NormalizedOptions} from 'got';
+import {hostname} from 'os';
+import {OTEL_EXPORTER_OTLP_ENDPOINT, OTEL_SERVICE_NAME} from './config';
+import * as log from './log';
+import {VERSION} from './version';
+
+const EXPORT_INTERVAL = 5000;
+const EXPORT_BATCH_SIZE = 512;
+// spans are dropped while the collector is unreachable for too long
+const QUEUE_LIMIT = 2048;
+
+const TRACEPARENT = /^00-([0-9a-f]{32})-([0-9a-f]{16})-([0-9a-f]{2})$/;
+
+// SPAN_KIND_SERVER and SPAN_KIND_CLIENT of the OTLP protocol
+const SERVER = 2;
+const CLIENT = 3;
+
+interface Span {
+  traceId: string;
+  spanId: string;
+  parentSpanId?: string;
+  name: string;
+  kind: number;
+  start: bigint;
+  attributes: Record<string, string | number>;
+}
+
+const current = new AsyncLocalStorage<Span>();
+const clientSpans = new WeakMap<NormalizedOptions, Span>();
+const queue: Record<string, unknown>[] = [];
+
+function now(): bigint {
+  return BigInt(Date.now()) * 1000000n;
+}
+
+function id(bytes: number): string {
+  return randomBytes(bytes).toString('hex');
+}
+
+function startSpan(name: string, kind: number, parent?: {traceId: string, spanId: string}): Span {
+  return {traceId: parent?.traceId ?? id(16), spanId: id(8), parentSpanId: parent?.spanId, name, kind, start: now(), attributes: {}};
+}
+
+/**
+ * Queues the span in the OTLP/JSON representation, errors are spans with a
+ * status of 500 and above (server) or 400 and above and failed requests (client).
+ */
+function endSpan(span: Span, error: boolean) {
+  queue.push({
+    traceId: span.traceId,
+    spanId: span.spanId,
+    ...(span.parentSpanId ? {parentSpanId: span.parentSpanId} : {}),
+    name: span.name,
+    kind: span.kind,
+    startTimeUnixNano: String(span.start),
+    endTimeUnixNano: String(now()),
+    attributes: Object.entries(span.attributes).map(([key, value]) => ({
+      key,
+      value: typeof value === 'number' ? {intValue: value} : {stringValue: value},
+    })),
+    status: {code: error ? 2 : 1},
+  });
+
+  if (queue.length > QUEUE_LIMIT) {
+    queue.splice(0, queue.length - QUEUE_LIMIT);
+  }
+}
+
+async function exportSpans() {
+  while (queue.length) {
+    const spans = queue.splice(0, EXPORT_BATCH_SIZE);
+
+    try {
+      // without the instrumented client, the export would be traced as well
+      await got.post(`${OTEL_EXPORTER_OTLP_ENDPOINT}/v1/traces`, {
+        timeout: EXPORT_INTERVAL,
+        json: {
+          resourceSpans: [{
+            resource: {
+              attributes: [
+                {key: 'service.name', value: {stringValue: OTEL_SERVICE_NAME}},
+                {key: 'service.version', value: {stringValue: VERSION.version}},
+                {key: 'host.name', value: {stringValue: hostname()}},
+              ],
+            },
+            scopeSpans: [{scope: {name: 'jitsi-openid'}, spans}],
+          }],
+        },
+      });
+    }
+    catch (error) {
+      log.warn(`Exporting ${spans.length} spans failed: ${error.message}`, {errorKind: 'otlp_export'});
+      return;
+    }
+  }
+}
+
+if (OTEL_EXPORTER_OTLP_ENDPOINT) {
+  setInterval(exportSpans, EXPORT_INTERVAL).unref();
+}
+
+/**
+ * Starts a server span for every request, continuing the trace of the proxy
+ * (W3C traceparent header). Requests the proxy didn't sample aren't traced.
+ * The span is named after the matched route (e.g. GET /room/:room), the
+ * query string isn't recorded, it may contain authorization codes.
+ */
+export function tracing(req: express.Request, res: express.Response, next: express.NextFunction) {
+  if (!OTEL_EXPORTER_OTLP_ENDPOINT) {
+    next();
+    return;
+  }
+
+  const [, traceId, spanId, flags] = TRACEPARENT.exec(req.header('traceparent') ?? '') ?? [];
+
+  if (flags && !(parseInt(flags, 16) & 1)) {
+    next();
+    return;
+  }
+
+  const span = startSpan(req.method, SERVER, traceId ? {traceId, spanId} : undefined);
+  span.attributes['http.method'] = req.method;
+  span.attributes['http.target'] = req.originalUrl.split('?')[0];
+  span.attributes['http.client_ip'] = req.ip;
+  span.attributes['request.id'] = req.id;
+  log.annotate({traceId: span.traceId});
+
+  res.on('finish', () => {
+    const route = req.route?.path;
+
+    if (typeof route === 'string') {
+      span.name = `${req.method} ${req.baseUrl}${route}`;
+    }
+
+    span.attributes['http.status_code'] = res.statusCode;
+    endSpan(span, res.statusCode >= 500);
+  });
+
+  current.run(span, next);
+}
+
+/**
+ * Starts a client span for an outgoing request (to the identity provider,
+ * prosody, ...) and propagates the trace using the traceparent header.
+ */
+export function startRequestSpan(options: NormalizedOptions) {
+  const parent = current.getStore();
+
+  if (!OTEL_EXPORTER_OTLP_ENDPOINT || !parent) {
+    return;
+  }
+
+  const span = startSpan(`${options.method} ${options.url.host}`, CLIENT, parent);
+  span.attributes['http.method'] = options.method;
+  span.attributes['http.url'] = `${options.url.origin}${options.url.pathname}`;
+  options.headers.traceparent = `00-${span.traceId}-${span.spanId}-01`;
+  clientSpans.set(options, span);
+}
+
+export function endRequestSpan(options: NormalizedOptions, status?: number) {
+  const span = clientSpans.get(options);
+
+  if (!span) {
+    return;
+  }
+
+  clientSpans.delete(options);
+
+  if (status !== undefined) {
+    span.attributes['http.status_code'] = status;
+  }
+
+  endSpan(span, status === undefined || status >= 400);
+}
@@ -0,0 +1,89 @@
+import got from 'got';
+import {readFileSync} from 'fs';
+import * as log from './log';
+
+// secrets which may be read from vault, the fields of the vault secret have the same names
+const VAULT_VARIABLES = ['JITSI_SECRET', 'CLIENT_SECRET', 'SECRET'];
+
+const KUBERNETES_TOKEN = '/var/run/secrets/kubernetes.io/serviceaccount/token';
+
+// read before the config is loaded, so they can only be set using the environment or flags
+const {
+  VAULT_ADDR,
+  VAULT_TOKEN,
+  VAULT_KUBERNETES_ROLE,
+  VAULT_KUBERNETES_MOUNT = 'kubernetes',
+  VAULT_SECRET_PATH,
+  VAULT_REFRESH_INTERVAL = '300',
+} = process.env;
+
+let token = VAULT_TOKEN;
+
+async function login() {
+  if (!VAULT_KUBERNETES_ROLE) {
+    return;
+  }
+
+  const {auth} = await got.post(`${VAULT_ADDR}/v1/auth/${VAULT_KUBERNETES_MOUNT}/login`, {
+    json: {role: VAULT_KUBERNETES_ROLE, jwt: readFileSync(KUBERNETES_TOKEN, 'utf8').trim()},
+  }).json<{auth: {client_token: string}}>();
+
+  token = auth.client_token;
+}
+
+async function renew() {
+  if (VAULT_KUBERNETES_ROLE) {
+    await login();
+    return;
+  }
+
+  await got.post(`${VAULT_ADDR}/v1/auth/token/renew-self`, {headers: {'x-vault-token': token}});
+}
+
+async function fetchSecrets() {
+  const {data} = await got(`${VAULT_ADDR}/v1/${VAULT_SECRET_PATH}`, {headers: {'x-vault-token': token}})
+    .json<{data: {data?: Record<string, string>} & Record<string, string>}>();
+
+  // kv version 2 nests the secret in data.data
+  const secret: Record<string, unknown> = data.data ?? data;
+
+  for (const name of VAULT_VARIABLES) {
+    const value = secret[name];
+
+    if (typeof value === 'string') {
+      process.env[name] = value;
+    }
+  }
+}
+
+/**
+ * Reads the secrets from vault (token or kubernetes auth) into the
+ * environment, before the configuration is loaded. Afterwards the token is
+ * renewed and the secrets are read again periodically, onRefresh is called to
+ * apply them.
+ */
+export async function loadVaultSecrets(onRefresh: () => void) {
+  if (!VAULT_ADDR || !VAULT_SECRET_PATH) {
+    return;
+  }
+
+  try {
+    await login();
+    await fetchSecrets();
+  }
+  catch (error) {
+    console.error(`Unable to read secrets from vault: ${error.message}`);
+    process.exit(1);
+  }
+
+  setInterval(async () => {
+    try {
+      await renew();
+      await fetchSecrets();
+      onRefresh();
+    }
+    catch (error) {
+      log.error(`Unable to refresh secrets from vault: ${error.message}`);
+    }
+  }, Number(VAULT_REFRESH_INTERVAL) * 1000).unref();
+}
@@ -0,0 +1,18 @@
+import * as express from 'express';
+
+// replaced by webpack at build time
+declare const BUILD_VERSION: string;
+declare const BUILD_COMMIT: string;
+declare const BUILD_TIMESTAMP: string;
+
+export const VERSION = {
+  version: BUILD_VERSION,
+  commit: BUILD_COMMIT,
+  buildTimestamp: BUILD_TIMESTAMP,
+};
+
+export const version = express.Router();
+
+version.get('/version', (req, res) => {
+  res.json(VERSION);
+});
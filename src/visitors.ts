@@ -0,0 +1,29 @@
+import {PARTICIPANT_ROLE, PARTICIPANT_ROLE_CLAIM, PROSODY_URL, VISITOR_THRESHOLD} from './config';
+import {claimValues} from './directory';
+import * as log from './log';
+import {roomSize} from './prosody';
+
+/**
+ * Users without PARTICIPANT_ROLE (if set) only join as visitors.
+ */
+export function lacksParticipantRole(claims: Record<string, unknown> | undefined): boolean {
+  return !!PARTICIPANT_ROLE && !claimValues(claims, PARTICIPANT_ROLE_CLAIM).includes(PARTICIPANT_ROLE);
+}
+
+/**
+ * Once a meeting has VISITOR_THRESHOLD participants, further users join as
+ * visitors. If prosody can't be asked, users join as participants.
+ */
+export async function isCrowded(room: string): Promise<boolean> {
+  if (!VISITOR_THRESHOLD || !PROSODY_URL) {
+    return false;
+  }
+
+  try {
+    return await roomSize(room) >= VISITOR_THRESHOLD;
+  }
+  catch (error) {
+    log.warn(`Resolving the size of ${room} failed: ${error.message}`, {room, errorKind: 'room_size'});
+    return false;
+  }
+}
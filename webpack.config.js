@@ -1,4 +1,6 @@
 const path = require('path');
+const {DefinePlugin} = require('webpack');
+const {version} = require('./package.json');
 
 module.exports = {
   entry: './src/index.ts',
@@ -15,6 +17,14 @@ module.exports = {
   resolve: {
     extensions: ['.tsx', '.ts', '.js'],
   },
+  plugins: [
+    new DefinePlugin({
+      BUILD_VERSION: JSON.stringify(version),
+      BUILD_COMMIT: JSON.stringify(process.env.GIT_COMMIT || 'unknown'),
+      BUILD_TIMESTAMP: JSON.stringify(new Date().toISOString()),
+      BUILD_RUNTIME_INSTRUMENTATION: JSON.stringify(process.env.RUNTIME_INSTRUMENTATION === 'true'),
+    }),
+  ],
   output: {
     filename: 'index.js',
     path: path.resolve(__dirname, 'dist'),